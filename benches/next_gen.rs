@@ -0,0 +1,100 @@
+//! Compares the single pass `compute_next_gen` against the per cell approach it replaced on large
+//! random soups. Run with `cargo bench`.
+
+use std::collections::HashSet;
+use std::time::{Duration, Instant};
+
+use rust_conway_gol::rule::Rule;
+use rust_conway_gol::topology::Topology;
+use rust_conway_gol::{compute_next_gen, Cell, NeighborIterator};
+
+const GENERATIONS: usize = 5;
+
+/// Fills a size x size square where each cell is alive with probability `density`, xorshift keeps
+/// the soup the same from run to run.
+fn soup(size: i64, density: f64, seed: u64) -> HashSet<Cell> {
+    let mut state = seed;
+    let mut cells = HashSet::new();
+    for row in 0..size {
+        for col in 0..size {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            if (state >> 11) as f64 / (1u64 << 53) as f64 <= density {
+                cells.insert((row, col));
+            }
+        }
+    }
+
+    cells
+}
+
+/// The previous implementation, every live cell counts its neighbors then every one of its dead
+/// neighbors counts theirs.
+fn per_cell_next_gen(current_gen: &HashSet<Cell>, rule: &Rule, topology: &Topology) -> HashSet<Cell> {
+    let mut next = HashSet::new();
+
+    for cell in current_gen.iter() {
+        let mut alive = 0;
+        let mut dead_neighbors = vec![];
+        for neighbor in NeighborIterator::new(*cell, *topology) {
+            if current_gen.contains(&neighbor) {
+                alive += 1;
+            } else {
+                dead_neighbors.push(neighbor);
+            }
+        }
+
+        if rule.survives(alive) {
+            next.insert(*cell);
+        }
+
+        for dead in dead_neighbors {
+            let alive = NeighborIterator::new(dead, *topology)
+                .filter(|neighbor| current_gen.contains(neighbor))
+                .count();
+            if rule.is_born(alive as u8) {
+                next.insert(dead);
+            }
+        }
+    }
+
+    next
+}
+
+fn time(
+    seed: &HashSet<Cell>,
+    step: fn(&HashSet<Cell>, &Rule, &Topology) -> HashSet<Cell>
+) -> (Duration, HashSet<Cell>) {
+    let rule = Rule::conway();
+    let topology = Topology::Plane;
+    let mut current = seed.clone();
+
+    let start = Instant::now();
+    for _ in 0..GENERATIONS {
+        current = step(&current, &rule, &topology);
+    }
+
+    (start.elapsed(), current)
+}
+
+fn main() {
+    for &(size, density) in &[(500, 0.5), (1000, 0.35), (1500, 0.25)] {
+        let seed = soup(size, density, 0x2545_f491_4f6c_dd1d);
+
+        let (per_cell, per_cell_result) = time(&seed, per_cell_next_gen);
+        let (single_pass, single_pass_result) = time(&seed, compute_next_gen);
+        assert!(per_cell_result == single_pass_result, "implementations disagree");
+
+        println!(
+            "{}x{} soup, {} cells, {} generations: per cell {:?}, single pass {:?}, {:.2}x faster",
+            size,
+            size,
+            seed.len(),
+            GENERATIONS,
+            per_cell,
+            single_pass,
+            per_cell.as_secs_f64() / single_pass.as_secs_f64()
+        );
+    }
+}
@@ -0,0 +1,199 @@
+//! Working out what a pattern settles into.
+//!
+//! Each generation is translated so its bounding box starts at the origin before being remembered,
+//! that way a spaceship shows up as the same shape coming back somewhere else. Generations are
+//! remembered by a hash of their cells rather than the cells themselves, so long runs only cost a
+//! few bytes per generation. A hash collision could report a repeat that didn't happen, with 64
+//! bits plus the population as the key that is not a practical concern.
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::hash::{Hash, Hasher};
+
+use crate::coordinate::Coordinate;
+use crate::{bounding_box, Cell};
+
+/// What a pattern became, generations are counted with the seed as generation 0.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    /// Every cell was dead by `generation`.
+    Died { generation: u64 },
+    /// Stopped changing from `generation` on.
+    StillLife { generation: u64 },
+    /// Repeats every `period` generations starting with `generation`.
+    Oscillator { period: u64, generation: u64 },
+    /// Repeats every `period` generations starting with `generation`, having moved `dx` columns
+    /// and `dy` rows each time.
+    Spaceship { period: u64, dx: i128, dy: i128, generation: u64 },
+    /// Nothing repeated in the `generations` looked at.
+    Undetermined { generations: u64 }
+}
+
+impl fmt::Display for Outcome {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Outcome::Died { generation } => write!(f, "died out at generation {}", generation),
+            Outcome::StillLife { generation } => write!(f, "still life from generation {}", generation),
+            Outcome::Oscillator { period, generation } => {
+                write!(f, "oscillator with period {} from generation {}", period, generation)
+            }
+            Outcome::Spaceship { period, dx, dy, generation } => write!(
+                f,
+                "{} spaceship with period {} moving ({}, {}) from generation {}",
+                speed(*dx, *dy, *period),
+                period,
+                dx,
+                dy,
+                generation
+            ),
+            Outcome::Undetermined { generations } => {
+                write!(f, "no repeat found in {} generations", generations)
+            }
+        }
+    }
+}
+
+/// Runs generations until one repeats, in place or shifted, or `max_generations` have been looked
+/// at.
+pub fn classify<C, I>(generations: I, max_generations: u64) -> Outcome
+where
+    C: Coordinate,
+    I: IntoIterator<Item = HashSet<Cell<C>>>
+{
+    let states = generations.into_iter().map(|cells| cells.into_iter().map(|cell| (cell, 1)).collect());
+    classify_states(states, max_generations)
+}
+
+/// Like `classify` for generations as the state of every cell, such as from
+/// `GOLGenerationIterator::into_states`. A generation only repeats if every cell is back in the
+/// same state, which matters for rules like Wireworld where the live cells alone move along a
+/// wire that doesn't.
+pub fn classify_states<C, I>(generations: I, max_generations: u64) -> Outcome
+where
+    C: Coordinate,
+    I: IntoIterator<Item = HashMap<Cell<C>, u8>>
+{
+    // Shape to the generation it was first seen and where its top left corner was
+    let mut seen: HashMap<(usize, u64), (u64, (i128, i128))> = HashMap::new();
+    let mut looked_at = 0;
+
+    for (generation, cells) in (0..max_generations).zip(generations) {
+        looked_at = generation + 1;
+
+        let (top, left) = match bounding_box(cells.keys()) {
+            Some(((top, left), _)) => (top.to_i128(), left.to_i128()),
+            None => return Outcome::Died { generation }
+        };
+
+        let shape_hash = cells
+            .iter()
+            .map(|(&(row, col), &state)| ((row.to_i128() - top, col.to_i128() - left), state))
+            .fold(0u64, |sum, cell| sum.wrapping_add(cell_hash(&cell)));
+
+        let key = (cells.len(), shape_hash);
+        if let Some(&(first_seen, (first_top, first_left))) = seen.get(&key) {
+            let period = generation - first_seen;
+            let (dx, dy) = (left - first_left, top - first_top);
+
+            return match (period, dx, dy) {
+                (1, 0, 0) => Outcome::StillLife { generation: first_seen },
+                (_, 0, 0) => Outcome::Oscillator { period, generation: first_seen },
+                _ => Outcome::Spaceship { period, dx, dy, generation: first_seen }
+            };
+        }
+
+        seen.insert(key, (generation, (top, left)));
+    }
+
+    Outcome::Undetermined { generations: looked_at }
+}
+
+/// Speed in the usual `c/N` notation, reduced so a ship moving 2 cells every 4 generations is
+/// `c/2`, followed by whether it travels orthogonally or diagonally. Oblique ships lead with the
+/// full displacement instead as in `(2,1)c/6`.
+pub fn speed(dx: i128, dy: i128, period: u64) -> String {
+    let (dx, dy) = (dx.abs(), dy.abs());
+    let period = period as i128;
+
+    if dx != 0 && dy != 0 && dx != dy {
+        return format!("({},{})c/{}", dx.max(dy), dx.min(dy), period);
+    }
+
+    let distance = dx.max(dy);
+    let divisor = gcd(distance, period);
+    let (distance, period) = (distance / divisor, period / divisor);
+    let direction = if dx == dy { "diagonal" } else { "orthogonal" };
+
+    if distance == 1 {
+        format!("c/{} {}", period, direction)
+    } else {
+        format!("{}c/{} {}", distance, period, direction)
+    }
+}
+
+fn gcd(a: i128, b: i128) -> i128 {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+/// Hash of a set of cells that doesn't depend on the order the set is iterated in.
+pub fn state_hash<'a, C: Coordinate>(cells: impl IntoIterator<Item = &'a Cell<C>>) -> u64 {
+    cells.into_iter().fold(0u64, |sum, cell| sum.wrapping_add(cell_hash(cell)))
+}
+
+fn cell_hash<T: Hash>(cell: &T) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    cell.hash(&mut hasher);
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rule::Rule;
+    use crate::tests::{cells, GLIDER};
+    use crate::topology::Topology;
+    use crate::GOLGenerationIterator;
+
+    fn outcome(seed: &[(i128, i128)]) -> Outcome {
+        let seed: Vec<Cell> = cells(seed).into_iter().collect();
+        classify(GOLGenerationIterator::new(seed, Rule::conway(), Topology::Plane), 100)
+    }
+
+    #[test]
+    fn outcomes() {
+        assert_eq!(outcome(&[(0, 0)]), Outcome::Died { generation: 1 });
+        assert_eq!(outcome(&[(0, 0), (0, 1), (1, 0), (1, 1)]), Outcome::StillLife { generation: 0 });
+        // Settles into a block after one generation
+        assert_eq!(outcome(&[(0, 0), (0, 1), (1, 0)]), Outcome::StillLife { generation: 1 });
+        assert_eq!(outcome(&[(0, 0), (0, 1), (0, 2)]), Outcome::Oscillator { period: 2, generation: 0 });
+        let quarter = [(0, 2), (0, 3), (0, 4), (2, 0), (3, 0), (4, 0), (2, 5), (3, 5), (4, 5), (5, 2), (5, 3), (5, 4)];
+        let pulsar: Vec<(i128, i128)> = quarter
+            .iter()
+            .flat_map(|&(row, col)| vec![(row, col), (row, 12 - col), (12 - row, col), (12 - row, 12 - col)])
+            .collect();
+        assert_eq!(outcome(&pulsar), Outcome::Oscillator { period: 3, generation: 0 });
+    }
+
+    #[test]
+    fn spaceships() {
+        assert_eq!(outcome(&GLIDER), Outcome::Spaceship { period: 4, dx: 1, dy: 1, generation: 0 });
+
+        let lwss = [(0, 1), (0, 4), (1, 0), (2, 0), (2, 4), (3, 0), (3, 1), (3, 2), (3, 3)];
+        assert_eq!(outcome(&lwss), Outcome::Spaceship { period: 4, dx: -2, dy: 0, generation: 0 });
+    }
+
+    #[test]
+    fn speeds() {
+        assert_eq!(speed(1, 1, 4), "c/4 diagonal");
+        assert_eq!(speed(-1, 1, 4), "c/4 diagonal");
+        assert_eq!(speed(-2, 0, 4), "c/2 orthogonal");
+        assert_eq!(speed(0, 2, 3), "2c/3 orthogonal");
+        assert_eq!(speed(2, -1, 6), "(2,1)c/6");
+        assert_eq!(speed(1, 2, 6), "(2,1)c/6");
+    }
+}
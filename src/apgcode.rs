@@ -0,0 +1,266 @@
+//! apgcodes, the object names used by apgsearch and Catagolue such as `xs4_33` for the block.
+//!
+//! The part after the underscore is the extended Wechsler format. The pattern is cut into strips
+//! 5 rows tall and each column of a strip is written as one of `0`-`9` and `a`-`v`, the top row of
+//! the strip being the lowest bit. Trailing blank columns are dropped, runs of blank columns are
+//! shortened to `w` for 2, `x` for 3 and `y` plus one of `0`-`9`/`a`-`z` for 4 to 39, and `z`
+//! moves on to the next strip. The canonical code of an object is the shortest encoding over all 8
+//! orientations and every phase, ties going to whichever comes first as a string.
+
+use std::collections::HashSet;
+use std::error::Error;
+use std::fmt;
+
+use crate::coordinate::Coordinate;
+use crate::{bounding_box, Cell};
+
+const DIGITS: &[u8; 36] = b"0123456789abcdefghijklmnopqrstuvwxyz";
+
+/// What kind of object a code names, the part before the underscore.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Prefix {
+    /// `xs` followed by the population.
+    StillLife,
+    /// `xp` followed by the period.
+    Oscillator(u64),
+    /// `xq` followed by the period.
+    Spaceship(u64)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ApgcodeError {
+    /// Not `xs`, `xp` or `xq` followed by a number and an underscore.
+    InvalidPrefix(String),
+    InvalidCharacter(char),
+    /// A `y` at the very end with no run length after it.
+    MissingRunLength,
+    /// An `xs` code whose cells don't add up to the population it starts with.
+    PopulationMismatch { expected: u64, found: u64 },
+    /// A cell lands outside the range of the coordinate type.
+    CoordinateOverflow
+}
+
+impl fmt::Display for ApgcodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ApgcodeError::InvalidPrefix(prefix) => write!(f, "'{}' is not an xs, xp or xq prefix", prefix),
+            ApgcodeError::InvalidCharacter(c) => write!(f, "unexpected character '{}'", c),
+            ApgcodeError::MissingRunLength => write!(f, "'y' needs a run length after it"),
+            ApgcodeError::PopulationMismatch { expected, found } => {
+                write!(f, "prefix says {} cells but the code has {}", expected, found)
+            }
+            ApgcodeError::CoordinateOverflow => write!(f, "pattern doesn't fit the coordinate type")
+        }
+    }
+}
+
+impl Error for ApgcodeError {}
+
+/// Canonical apgcode of an object given every phase of it in order, one phase for a still life.
+pub fn encode<'a, C: Coordinate>(prefix: Prefix, phases: impl IntoIterator<Item = &'a HashSet<Cell<C>>>) -> String {
+    let mut population = 0;
+    let wechsler = phases
+        .into_iter()
+        .inspect(|phase| population = phase.len())
+        .flat_map(|phase| (0..8).map(move |orientation| wechsler(&orient(phase, orientation))))
+        .min_by(|a, b| a.len().cmp(&b.len()).then(a.cmp(b)))
+        .unwrap_or_default();
+
+    let prefix = match prefix {
+        Prefix::StillLife => format!("xs{}", population),
+        Prefix::Oscillator(period) => format!("xp{}", period),
+        Prefix::Spaceship(period) => format!("xq{}", period)
+    };
+
+    format!("{}_{}", prefix, wechsler)
+}
+
+/// Splits an apgcode into its prefix and the cells of the phase it encodes, with the top left of
+/// the strips at the origin.
+pub fn decode<C: Coordinate>(code: &str) -> Result<(Prefix, Vec<Cell<C>>), ApgcodeError> {
+    let invalid = || ApgcodeError::InvalidPrefix(code.split('_').next().unwrap_or_default().to_string());
+
+    let (prefix, wechsler) = code.split_once('_').ok_or_else(invalid)?;
+    let number: u64 = prefix.get(2..).and_then(|digits| digits.parse().ok()).ok_or_else(invalid)?;
+    let cells = decode_wechsler(wechsler)?;
+
+    let prefix = match prefix.get(..2) {
+        Some("xs") if cells.len() as u64 != number => {
+            return Err(ApgcodeError::PopulationMismatch { expected: number, found: cells.len() as u64 });
+        }
+        Some("xs") => Prefix::StillLife,
+        Some("xp") => Prefix::Oscillator(number),
+        Some("xq") => Prefix::Spaceship(number),
+        _ => return Err(invalid())
+    };
+
+    Ok((prefix, cells))
+}
+
+/// Extended Wechsler encoding of the cells as they are, no orientation or phase is tried.
+pub fn wechsler<C: Coordinate>(cells: &[Cell<C>]) -> String {
+    let ((top, left), (bottom, right)) = match bounding_box(cells) {
+        Some(((top, left), (bottom, right))) => (
+            (top.to_i128(), left.to_i128()),
+            (bottom.to_i128(), right.to_i128())
+        ),
+        None => return String::new()
+    };
+
+    let strips = ((bottom - top) / 5 + 1) as usize;
+    let width = (right - left + 1) as usize;
+    let mut columns = vec![vec![0u8; width]; strips];
+    for &(row, col) in cells {
+        let (row, col) = ((row.to_i128() - top) as usize, (col.to_i128() - left) as usize);
+        columns[row / 5][col] |= 1 << (row % 5);
+    }
+
+    let mut code = String::new();
+    for (index, strip) in columns.iter().enumerate() {
+        if index > 0 {
+            code.push('z');
+        }
+
+        let mut blanks = 0;
+        for &column in strip {
+            if column == 0 {
+                blanks += 1;
+                continue;
+            }
+
+            push_blanks(&mut code, blanks);
+            blanks = 0;
+            code.push(DIGITS[column as usize] as char);
+        }
+    }
+
+    code
+}
+
+/// Writes a run of blank columns as short as it goes.
+fn push_blanks(code: &mut String, mut blanks: usize) {
+    while blanks > 39 {
+        code.push_str("yz");
+        blanks -= 39;
+    }
+
+    match blanks {
+        0 => {}
+        1 => code.push('0'),
+        2 => code.push('w'),
+        3 => code.push('x'),
+        blanks => {
+            code.push('y');
+            code.push(DIGITS[blanks - 4] as char);
+        }
+    }
+}
+
+/// Cells of an extended Wechsler string, the part of an apgcode after the underscore.
+pub fn decode_wechsler<C: Coordinate>(wechsler: &str) -> Result<Vec<Cell<C>>, ApgcodeError> {
+    let mut cells = vec![];
+    let (mut strip, mut col) = (0i128, 0i128);
+
+    let mut chars = wechsler.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            'w' => col += 2,
+            'x' => col += 3,
+            'y' => {
+                let run = chars.next().ok_or(ApgcodeError::MissingRunLength)?;
+                col += 4 + digit(run).ok_or(ApgcodeError::InvalidCharacter(run))? as i128;
+            }
+            'z' => {
+                strip += 1;
+                col = 0;
+            }
+            c => {
+                let column = digit(c).filter(|&column| column < 32).ok_or(ApgcodeError::InvalidCharacter(c))?;
+                for bit in 0..5 {
+                    if column & (1 << bit) != 0 {
+                        let row = C::from_i128(strip * 5 + bit).ok_or(ApgcodeError::CoordinateOverflow)?;
+                        cells.push((row, C::from_i128(col).ok_or(ApgcodeError::CoordinateOverflow)?));
+                    }
+                }
+                col += 1;
+            }
+        }
+    }
+
+    Ok(cells)
+}
+
+fn digit(c: char) -> Option<usize> {
+    DIGITS.iter().position(|&digit| digit as char == c)
+}
+
+/// The cells turned to one of the 8 orientations of the square.
+fn orient<C: Coordinate>(cells: &HashSet<Cell<C>>, orientation: u8) -> Vec<Cell<i128>> {
+    cells
+        .iter()
+        .map(|&(row, col)| {
+            let (row, col) = (row.to_i128(), col.to_i128());
+            match orientation {
+                0 => (row, col),
+                1 => (col, -row),
+                2 => (-row, -col),
+                3 => (-col, row),
+                4 => (row, -col),
+                5 => (-row, col),
+                6 => (col, row),
+                _ => (-col, -row)
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rule::Rule;
+    use crate::topology::Topology;
+    use crate::GOLGenerationIterator;
+
+    /// Every phase of an object starting from `cells`, one for a still life.
+    fn phases(prefix: Prefix, cells: Vec<Cell>) -> Vec<HashSet<Cell>> {
+        let period = match prefix {
+            Prefix::StillLife => 1,
+            Prefix::Oscillator(period) | Prefix::Spaceship(period) => period as usize
+        };
+        GOLGenerationIterator::new(cells, Rule::conway(), Topology::Plane).take(period).collect()
+    }
+
+    #[test]
+    fn known_objects() {
+        let objects: [(Prefix, &[Cell]); 7] = [
+            (Prefix::StillLife, &[(0, 0), (0, 1), (1, 0), (1, 1)]),
+            (Prefix::StillLife, &[(0, 1), (0, 2), (1, 0), (1, 3), (2, 1), (2, 2)]),
+            (Prefix::StillLife, &[(0, 1), (0, 2), (1, 0), (1, 3), (2, 1), (2, 3), (3, 2)]),
+            (Prefix::Oscillator(2), &[(0, 0), (0, 1), (0, 2)]),
+            (Prefix::Oscillator(2), &[(0, 0), (0, 1), (1, 0), (2, 3), (3, 2), (3, 3)]),
+            (Prefix::Spaceship(4), &[(0, 1), (1, 2), (2, 0), (2, 1), (2, 2)]),
+            (Prefix::Spaceship(4), &[(0, 1), (0, 4), (1, 0), (2, 0), (2, 4), (3, 0), (3, 1), (3, 2), (3, 3)])
+        ];
+        let codes = ["xs4_33", "xs6_696", "xs7_2596", "xp2_7", "xp2_318c", "xq4_153", "xq4_6frc"];
+
+        for ((prefix, cells), code) in objects.iter().zip(codes.iter()) {
+            assert_eq!(encode(*prefix, &phases(*prefix, cells.to_vec())), *code);
+        }
+    }
+
+    #[test]
+    fn decode_round_trips() {
+        for code in ["xs4_33", "xs6_696", "xs7_2596", "xp2_7", "xp2_318c", "xq4_153", "xq4_6frc", "xs8_6996"] {
+            let (prefix, cells) = decode::<i64>(code).unwrap();
+            assert_eq!(encode(prefix, &phases(prefix, cells)), code);
+        }
+    }
+
+    #[test]
+    fn decode_errors() {
+        assert_eq!(decode::<i64>("xs5_33"), Err(ApgcodeError::PopulationMismatch { expected: 5, found: 4 }));
+        assert_eq!(decode::<i64>("yl144_1"), Err(ApgcodeError::InvalidPrefix("yl144".to_string())));
+        assert_eq!(decode::<i64>("xp2_7y"), Err(ApgcodeError::MissingRunLength));
+        assert_eq!(decode::<i64>("xp2_7!"), Err(ApgcodeError::InvalidCharacter('!')));
+    }
+}
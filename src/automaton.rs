@@ -0,0 +1,180 @@
+//! Rules given as a state machine rather than birth and survival, where each cell's next state
+//! depends on its own state and the states of the 8 cells around it. Wireworld, Brian's Brain and
+//! rule tables such as Langton's loops all fit.
+//!
+//! State 0 is the background and has to stay that way with nothing but state 0 around it, so
+//! only cells that aren't in state 0 and their neighbors are looked at each step.
+
+use std::collections::{HashMap, HashSet};
+
+use crate::coordinate::Coordinate;
+use crate::engine::{Engine, EngineError};
+use crate::rule::Rule;
+use crate::topology::Topology;
+use crate::{Cell, NeighborIterator, NEIGHBOR_OFFSETS};
+
+/// A rule working on cell states directly.
+pub trait StateRule {
+    /// Number of states including state 0, up to 256.
+    fn states(&self) -> u16;
+
+    /// State a cell in `state` moves to. `neighbors` are the states of the cells around it row by
+    /// row from the top left, the same order as `NeighborIterator`.
+    fn next_state(&self, state: u8, neighbors: [u8; 8]) -> u8;
+}
+
+/// Wireworld, where electrons made of a head and a tail run along conductors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Wireworld;
+
+impl Wireworld {
+    pub const EMPTY: u8 = 0;
+    pub const HEAD: u8 = 1;
+    pub const TAIL: u8 = 2;
+    pub const CONDUCTOR: u8 = 3;
+}
+
+/// Heads become tails and tails conductors, a conductor becomes a head when 1 or 2 of its
+/// neighbors are heads.
+impl StateRule for Wireworld {
+    fn states(&self) -> u16 {
+        4
+    }
+
+    fn next_state(&self, state: u8, neighbors: [u8; 8]) -> u8 {
+        match state {
+            Wireworld::HEAD => Wireworld::TAIL,
+            Wireworld::TAIL => Wireworld::CONDUCTOR,
+            Wireworld::CONDUCTOR => {
+                let heads = neighbors.iter().filter(|&&neighbor| neighbor == Wireworld::HEAD).count();
+                if heads == 1 || heads == 2 {
+                    Wireworld::HEAD
+                } else {
+                    Wireworld::CONDUCTOR
+                }
+            }
+            state => state
+        }
+    }
+}
+
+/// Life-like and Generations rules, with only live neighbors counting. Brian's Brain is
+/// `B2/S/C3`.
+impl StateRule for Rule {
+    fn states(&self) -> u16 {
+        Rule::states(self) as u16
+    }
+
+    fn next_state(&self, state: u8, neighbors: [u8; 8]) -> u8 {
+        let alive = neighbors
+            .iter()
+            .enumerate()
+            .filter(|&(_, &neighbor)| neighbor == 1)
+            .fold(0u8, |mask, (index, _)| mask | 1 << index);
+
+        match state {
+            0 if self.is_born_with(alive) => 1,
+            0 => 0,
+            1 if self.survives_with(alive) => 1,
+            state => self.decay(state)
+        }
+    }
+}
+
+/// Runs any `StateRule` from a map of cell states.
+pub struct StateEngine<C: Coordinate, R: StateRule> {
+    /// Every cell not in state 0
+    current_gen: HashMap<Cell<C>, u8>,
+    rule: R,
+    topology: Topology<C>,
+    /// Next state for each (state, neighbors) seen so far, patterns keep running into the same
+    /// handful and rule tables are slow to search
+    known: HashMap<(u8, [u8; 8]), u8>
+}
+
+impl<C: Coordinate, R: StateRule> StateEngine<C, R> {
+    /// Cells in state 0 are dropped and seed cells outside of a torus board are wrapped onto it.
+    /// Cells in states the rule doesn't have are an error.
+    pub fn new(seed: HashMap<Cell<C>, u8>, rule: R, topology: Topology<C>) -> Result<StateEngine<C, R>, EngineError> {
+        if let Some(&state) = seed.values().find(|&&state| state as u16 >= rule.states()) {
+            return Err(EngineError::InvalidState(state, rule.states()));
+        }
+
+        let gen_zero = seed
+            .into_iter()
+            .filter(|&(_, state)| state != 0)
+            .map(|(cell, state)| (topology.wrap(cell), state))
+            .collect();
+        Ok(StateEngine {
+            current_gen: gen_zero,
+            rule,
+            topology,
+            known: HashMap::new()
+        })
+    }
+
+    fn neighbors(&self, cell: Cell<C>) -> [u8; 8] {
+        let mut neighbors = [0; 8];
+        for (neighbor, offset) in neighbors.iter_mut().zip(NEIGHBOR_OFFSETS.iter()) {
+            if let Some(cell) = self.topology.neighbor(cell, *offset) {
+                *neighbor = self.current_gen.get(&cell).copied().unwrap_or(0);
+            }
+        }
+        neighbors
+    }
+}
+
+impl<C: Coordinate, R: StateRule> Engine<C> for StateEngine<C, R> {
+    /// Cells in state 1.
+    fn cells(&self) -> HashSet<Cell<C>> {
+        self.current_gen.iter().filter(|&(_, &state)| state == 1).map(|(&cell, _)| cell).collect()
+    }
+
+    fn states(&self) -> HashMap<Cell<C>, u8> {
+        self.current_gen.clone()
+    }
+
+    fn population(&self) -> u128 {
+        self.current_gen.values().filter(|&&state| state == 1).count() as u128
+    }
+
+    fn step(&mut self) {
+        let mut candidates: HashSet<Cell<C>> = HashSet::with_capacity(self.current_gen.len() * 4);
+        for &cell in self.current_gen.keys() {
+            candidates.insert(cell);
+            candidates.extend(NeighborIterator::new(cell, self.topology));
+        }
+
+        let mut next_gen = HashMap::with_capacity(self.current_gen.len());
+        for cell in candidates {
+            let key = (self.current_gen.get(&cell).copied().unwrap_or(0), self.neighbors(cell));
+            let rule = &self.rule;
+            let state = *self.known.entry(key).or_insert_with(|| rule.next_state(key.0, key.1));
+            if state != 0 {
+                next_gen.insert(cell, state);
+            }
+        }
+
+        self.current_gen = next_gen;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::HashSetEngine;
+
+    #[test]
+    fn rule_matches_hashset_engine() {
+        let rule: Rule = "B2-a/S12".parse().unwrap();
+        let seed: Vec<Cell> = vec![(0, 1), (1, 2), (2, 0), (2, 1), (2, 2), (5, 5), (5, 6), (6, 8), (7, 5)];
+
+        let mut expected = HashSetEngine::new(seed.clone(), rule, Topology::Plane);
+        let mut engine = StateEngine::new(seed.into_iter().map(|cell| (cell, 1)).collect(), rule, Topology::Plane).unwrap();
+        for generation in 0..50 {
+            assert_eq!(engine.states(), expected.states(), "generation {}", generation);
+            engine.step();
+            expected.step();
+        }
+    }
+}
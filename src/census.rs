@@ -0,0 +1,266 @@
+//! Soup searches in the style of apgsearch.
+//!
+//! Each soup is run until its population has been periodic for a while, at which point whatever
+//! is left, the ash, is split into objects. Every object is then run on its own to find out what
+//! it is and counted under its apgcode, which doesn't depend on its orientation or phase.
+//! Touching cells make an object, unless the pieces only work together in which case every piece
+//! within 2 cells of another is taken as one.
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::thread;
+
+use crate::analysis::{self, Outcome};
+use crate::apgcode::{self, Prefix};
+use crate::engine::{Backend, Engine, HashSetEngine};
+use crate::objects::{self, Connectivity};
+use crate::rule::Rule;
+use crate::soup::Soup;
+use crate::topology::Topology;
+use crate::{Cell, CellCoordinate, GOLGenerationIterator};
+
+/// Longest population period recognised as the ash having settled.
+const MAX_PERIOD: usize = 60;
+
+/// Generations the population has to keep repeating for before the soup counts as settled.
+const SETTLE_WINDOW: usize = 3 * MAX_PERIOD;
+
+/// Code objects are counted under when they don't repeat on their own.
+pub const UNIDENTIFIED: &str = "unidentified";
+
+/// How soups are made and how long they get to settle.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Search {
+    pub soup: Soup,
+    pub rule: Rule,
+    /// Soups still changing after this many generations are set aside as unsettled
+    pub max_generations: u64
+}
+
+impl Search {
+    /// Runs soups `prefix0` through `prefix{count - 1}` spread over `threads` threads. The census
+    /// comes out the same whatever the number of threads.
+    pub fn run(&self, prefix: &str, count: u64, threads: usize) -> Census {
+        let threads = threads.max(1) as u64;
+
+        thread::scope(|scope| {
+            let workers: Vec<_> = (0..threads)
+                .map(|first| {
+                    scope.spawn(move || {
+                        let mut census = Census::new();
+                        for index in (first..count).step_by(threads as usize) {
+                            self.run_soup(&format!("{}{}", prefix, index), &mut census);
+                        }
+                        census
+                    })
+                })
+                .collect();
+
+            workers.into_iter().fold(Census::new(), |mut total, worker| {
+                total.merge(worker.join().expect("census thread panicked"));
+                total
+            })
+        })
+    }
+
+    /// Runs the soup for one seed and adds what it left behind to the census.
+    pub fn run_soup(&self, seed: &str, census: &mut Census) {
+        census.soups += 1;
+
+        let ash = match settle(self.soup.generate(seed), self.rule, self.max_generations) {
+            Some(ash) => ash,
+            None => {
+                census.unsettled.push(seed.to_string());
+                return;
+            }
+        };
+
+        // Objects a cell apart usually interact, if the touching pieces aren't objects on their
+        // own what they make together is
+        for group in objects::separate(&ash, Connectivity::Distance2) {
+            let parts: Option<Vec<String>> = objects::separate(&group.cells.iter().copied().collect(), Connectivity::Adjacent)
+                .iter()
+                .map(|part| Some(identify(&part.cells, self.rule)).filter(|code| code != UNIDENTIFIED))
+                .collect();
+
+            match parts {
+                Some(parts) => parts.into_iter().for_each(|code| census.add(code, seed)),
+                None => census.add(identify(&group.cells, self.rule), seed)
+            }
+        }
+    }
+}
+
+/// Tally of the objects found in a run of soups.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Census {
+    soups: u64,
+    /// Object code to how many were seen and the lowest numbered soup one turned up in
+    objects: HashMap<String, (u64, String)>,
+    /// Seeds of soups that hadn't settled by the generation limit
+    unsettled: Vec<String>
+}
+
+impl Census {
+    pub fn new() -> Census {
+        Census::default()
+    }
+
+    /// Number of soups run, settled or not.
+    pub fn soups(&self) -> u64 {
+        self.soups
+    }
+
+    /// Every object found as (code, count, example seed), most common first.
+    pub fn objects(&self) -> Vec<(&str, u64, &str)> {
+        let mut objects: Vec<_> = self
+            .objects
+            .iter()
+            .map(|(code, (count, seed))| (code.as_str(), *count, seed.as_str()))
+            .collect();
+        objects.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+        objects
+    }
+
+    /// Objects seen no more than `max_count` times, the rarest first.
+    pub fn rare(&self, max_count: u64) -> Vec<(&str, u64, &str)> {
+        let mut rare: Vec<_> = self.objects().into_iter().filter(|object| object.1 <= max_count).collect();
+        rare.reverse();
+        rare
+    }
+
+    pub fn unsettled(&self) -> &[String] {
+        &self.unsettled
+    }
+
+    pub fn add(&mut self, code: String, seed: &str) {
+        let (count, example) = self.objects.entry(code).or_insert_with(|| (0, seed.to_string()));
+        *count += 1;
+        if earlier(seed, example) {
+            *example = seed.to_string();
+        }
+    }
+
+    /// Adds in the counts from another census, such as one from another thread.
+    pub fn merge(&mut self, other: Census) {
+        self.soups += other.soups;
+
+        for (code, (count, seed)) in other.objects {
+            let (total, example) = self.objects.entry(code).or_insert_with(|| (0, seed.clone()));
+            *total += count;
+            if earlier(&seed, example) {
+                *example = seed;
+            }
+        }
+
+        self.unsettled.extend(other.unsettled);
+        self.unsettled.sort_by(|a, b| a.len().cmp(&b.len()).then(a.cmp(b)));
+    }
+}
+
+/// Whether seed `a` was numbered before `b`, seeds sharing a prefix sort by length first.
+fn earlier(a: &str, b: &str) -> bool {
+    (a.len(), a) < (b.len(), b)
+}
+
+/// Runs a soup until its population has been periodic for `SETTLE_WINDOW` generations and returns
+/// the final generation, None if that didn't happen within `max_generations`.
+pub fn settle(seed: Vec<Cell>, rule: Rule, max_generations: u64) -> Option<HashSet<Cell>> {
+    // Only the population is needed until the end which the tiled engine counts without building
+    // a HashSet every generation, for the rules it can run
+    let mut engine: Box<dyn Engine<CellCoordinate>> = match Backend::Tiled.create(seed.clone(), rule, Topology::Plane) {
+        Ok(engine) => engine,
+        Err(_) => Box::new(HashSetEngine::new(seed, rule, Topology::Plane))
+    };
+    let mut populations = VecDeque::with_capacity(SETTLE_WINDOW + MAX_PERIOD);
+
+    for generation in 0..=max_generations {
+        if populations.len() == SETTLE_WINDOW + MAX_PERIOD {
+            populations.pop_front();
+        }
+        populations.push_back(engine.population());
+
+        // Checking every generation would spend more time here than on stepping
+        if generation % MAX_PERIOD as u64 == 0 && populations.len() == SETTLE_WINDOW + MAX_PERIOD {
+            let periodic = (1..=MAX_PERIOD).any(|period| {
+                (MAX_PERIOD..populations.len()).all(|index| populations[index] == populations[index - period])
+            });
+            if periodic {
+                return Some(engine.cells());
+            }
+        }
+
+        engine.step();
+    }
+
+    None
+}
+
+/// apgcode of an object run on its own, anything that doesn't repeat from its first generation is
+/// `UNIDENTIFIED`.
+pub fn identify(object: &[Cell], rule: Rule) -> String {
+    let mut generations: Vec<HashSet<Cell>> = vec![];
+    let outcome = analysis::classify(
+        GOLGenerationIterator::new(object.to_vec(), rule, Topology::Plane).inspect(|cells| generations.push(cells.clone())),
+        MAX_PERIOD as u64 * 2 + 1
+    );
+
+    let (prefix, period) = match outcome {
+        Outcome::StillLife { generation: 0 } => (Prefix::StillLife, 1),
+        Outcome::Oscillator { period, generation: 0 } => (Prefix::Oscillator(period), period),
+        Outcome::Spaceship { period, generation: 0, .. } => (Prefix::Spaceship(period), period),
+        _ => return UNIDENTIFIED.to_string()
+    };
+
+    apgcode::encode(prefix, &generations[..period as usize])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::soup::Symmetry;
+    use crate::tests::GLIDER;
+
+    fn search() -> Search {
+        Search { soup: Soup::new(5, 5, 0.5, Symmetry::C1).unwrap(), rule: Rule::conway(), max_generations: 2000 }
+    }
+
+    #[test]
+    fn soup_settling_to_a_block_and_a_blinker() {
+        let mut census = Census::new();
+        search().run_soup("k_ash106", &mut census);
+
+        assert_eq!(census.soups(), 1);
+        assert_eq!(census.objects(), vec![("xp2_7", 1, "k_ash106"), ("xs4_33", 1, "k_ash106")]);
+        assert!(census.unsettled().is_empty());
+    }
+
+    #[test]
+    fn settled_ash() {
+        // A pre-block and a blinker far enough apart not to meet
+        let seed = vec![(0, 0), (0, 1), (1, 0), (10, 10), (10, 11), (10, 12)];
+        let ash = settle(seed, Rule::conway(), 1000).unwrap();
+
+        assert_eq!(ash.len(), 7);
+        assert!(ash.contains(&(1, 1)));
+        assert_eq!(settle(vec![(0, 0), (0, 1), (0, 2), (1, 0), (2, 1)], Rule::conway(), 10), None);
+    }
+
+    #[test]
+    fn identified_objects() {
+        let glider: Vec<Cell> = GLIDER.iter().map(|&(row, col)| (row as i64, col as i64)).collect();
+
+        assert_eq!(identify(&[(0, 0), (0, 1), (1, 0), (1, 1)], Rule::conway()), "xs4_33");
+        assert_eq!(identify(&[(0, 0), (1, 0), (2, 0)], Rule::conway()), "xp2_7");
+        assert_eq!(identify(&glider, Rule::conway()), "xq4_153");
+        assert_eq!(identify(&[(0, 0), (0, 1), (1, 0)], Rule::conway()), UNIDENTIFIED);
+    }
+
+    #[test]
+    fn threads_do_not_change_the_census() {
+        let one = search().run("k_ash", 40, 1);
+        let three = search().run("k_ash", 40, 3);
+
+        assert_eq!(one.soups(), 40);
+        assert_eq!(one, three);
+    }
+}
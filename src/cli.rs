@@ -0,0 +1,681 @@
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::thread;
+
+use rust_conway_gol::analysis::{self, Outcome};
+use rust_conway_gol::apgcode::{self, Prefix};
+use rust_conway_gol::census::Search;
+use rust_conway_gol::engine::{Backend, EngineError};
+use rust_conway_gol::pattern::{self, Format, Pattern};
+use rust_conway_gol::render::{render_hex_states_text, render_states_text, Viewport};
+use rust_conway_gol::rule::{AnyRule, Neighborhood, Rule};
+use rust_conway_gol::soup::{Soup, Symmetry};
+use rust_conway_gol::table::Table;
+use rust_conway_gol::topology::Topology;
+use rust_conway_gol::{bounding_box, CellCoordinate, GOLGenerationIterator};
+
+use crate::tui::{Editor, Terminal, Viewer};
+
+pub const USAGE: &str = "\
+usage:
+    rust_conway_gol run <pattern> [--generations N] [--rule RULE] [--rule-file FILE] [--viewport X,Y,W,H]
+                        [--torus W,H] [--backend hashset|hashlife|tiled|parallel] [--threads N]
+                        [--step-pow2 K]
+    rust_conway_gol convert <input> <output>
+    rust_conway_gol info <pattern>
+    rust_conway_gol analyze <pattern> [--rule RULE] [--rule-file FILE] [--torus W,H] [--max-generations N]
+    rust_conway_gol tui <pattern> [--rule RULE] [--rule-file FILE] [--torus W,H] [--backend B] [--threads N]
+    rust_conway_gol edit [pattern] [--rule RULE] [--rule-file FILE]
+    rust_conway_gol soup <seed> <output> [--size W,H] [--density P] [--symmetry C1|C2|C4|D4|D8]
+    rust_conway_gol census [--soups N] [--prefix P] [--size W,H] [--density P] [--symmetry S]
+                           [--rule RULE] [--threads N] [--max-generations N] [--rare N]
+
+Patterns can be RLE (.rle), plaintext (.cells) or Life 1.05/1.06 (.lif), the output format of
+convert is picked from its extension.
+
+--rule takes Life-like rules such as B3/S23 or with Hensel letters B2-a/S12, Generations rules
+such as B2/S/C3 and Larger than Life rules such as R5,C0,M1,S34..58,B34..45,NM, the last only
+with the hashset backend on the plane. Ending a Life-like or Generations rule in V or H uses the
+von Neumann or hexagonal neighborhood, run draws hexagonal rules with each row shifted left.
+WireWorld and BriansBrain can be given by name. --rule-file loads a Golly rule table (.rule) such
+as Langtons-Loops.rule, these and WireWorld only run with the hashset backend. RLE patterns for
+them use . for empty cells and A, B, C... for states 1, 2, 3...
+
+run prints the seed and the next N generations, with --step-pow2 each of those is 2^K generations
+apart which the hashlife backend can compute without visiting the ones in between.
+
+tui plays the pattern full screen: space plays and pauses, n steps once, + and - change the speed,
+the arrow keys pan, z zooms out and Z back in down to braille at 2x4 cells per character, f keeps
+the whole pattern in view and q quits.
+
+edit opens a pattern, or a new file, for editing: the arrow keys move the cursor and space toggles
+the cell under it. v starts a selection which y copies, x cuts, r rotates and m or M mirror left to
+right or top to bottom, p pastes at the cursor. w saves as RLE, g plays the current pattern and q
+quits.
+
+soup writes a random 16x16 soup at density 0.5 unless told otherwise, the same seed always gives
+the same soup.
+
+analyze also prints the apgcode of whatever the pattern settles into.
+
+census runs soups named <prefix>0 to <prefix>N-1 until they settle and counts the objects left by
+apgcode, objects seen --rare times or fewer are listed again with the first soup they came from.";
+
+/// Why a command failed, each maps to its own exit code so scripts can tell them apart.
+#[derive(Debug)]
+pub enum CliError {
+    /// The arguments themselves were wrong, exit code 2.
+    Usage(String),
+    /// The arguments were fine but the work couldn't be done, such as an unreadable file,
+    /// exit code 1.
+    Failed(String)
+}
+
+impl CliError {
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            CliError::Usage(_) => 2,
+            CliError::Failed(_) => 1
+        }
+    }
+}
+
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CliError::Usage(message) | CliError::Failed(message) => write!(f, "{}", message)
+        }
+    }
+}
+
+fn usage(message: impl Into<String>) -> CliError {
+    CliError::Usage(message.into())
+}
+
+/// Arguments split into positionals and `--name value` options, in the order they were given.
+struct Arguments {
+    positional: Vec<String>,
+    options: Vec<(String, String)>
+}
+
+impl Arguments {
+    fn parse(args: &[String], known_options: &[&str]) -> Result<Arguments, CliError> {
+        let mut positional = vec![];
+        let mut options = vec![];
+
+        let mut iter = args.iter();
+        while let Some(arg) = iter.next() {
+            match arg.strip_prefix("--") {
+                Some(name) if known_options.contains(&name) => {
+                    let value = iter.next().ok_or_else(|| usage(format!("--{} needs a value", name)))?;
+                    options.push((name.to_string(), value.clone()));
+                }
+                Some(name) => return Err(usage(format!("unknown option --{}", name))),
+                None => positional.push(arg.clone())
+            }
+        }
+
+        Ok(Arguments { positional, options })
+    }
+
+    /// Last value given for an option so later flags override earlier ones.
+    fn option(&self, name: &str) -> Option<&str> {
+        self.options.iter().rev().find(|(option, _)| option == name).map(|(_, value)| value.as_str())
+    }
+
+    fn expect_positional(&self, names: &[&str]) -> Result<(), CliError> {
+        if self.positional.len() != names.len() {
+            return Err(usage(format!("expected {}", names.join(" "))));
+        }
+
+        Ok(())
+    }
+}
+
+pub fn run(args: &[String]) -> Result<(), CliError> {
+    let (command, rest) = match args.split_first() {
+        Some((command, rest)) => (command.as_str(), rest),
+        None => return Err(usage("missing command"))
+    };
+
+    match command {
+        "run" => run_pattern(rest),
+        "convert" => convert(rest),
+        "info" => info(rest),
+        "analyze" => analyze(rest),
+        "tui" => tui(rest),
+        "edit" => edit(rest),
+        "soup" => soup(rest),
+        "census" => census(rest),
+        "help" | "--help" | "-h" => {
+            println!("{}", USAGE);
+            Ok(())
+        }
+        command => Err(usage(format!("unknown command '{}'", command)))
+    }
+}
+
+/// Everything `run` takes from its arguments, worked out before any file is read.
+#[derive(Debug, Clone, PartialEq)]
+struct RunOptions {
+    pattern: String,
+    generations: usize,
+    rule: RuleOption,
+    backend: Backend,
+    topology: Topology,
+    step_log2: u32,
+    /// None to show wherever the seed is
+    viewport: Option<Viewport>
+}
+
+fn parse_run_options(args: &[String]) -> Result<RunOptions, CliError> {
+    let args = Arguments::parse(args, &["generations", "rule", "rule-file", "viewport", "torus", "backend", "threads", "step-pow2"])?;
+    args.expect_positional(&["<pattern>"])?;
+
+    let generations: usize = match args.option("generations") {
+        Some(value) => value.parse().map_err(|_| usage(format!("invalid --generations '{}'", value)))?,
+        None => 10
+    };
+
+    let step_log2: u32 = match args.option("step-pow2") {
+        Some(value) => value
+            .parse()
+            .ok()
+            .filter(|k| *k < 64)
+            .ok_or_else(|| usage(format!("--step-pow2 must be between 0 and 63, got '{}'", value)))?,
+        None => 0
+    };
+
+    let viewport = match args.option("viewport") {
+        Some(value) => match parse_numbers(value)?[..] {
+            [x, y, width, height] if width > 0 && height > 0 => Some(Viewport {
+                top: y,
+                left: x,
+                width: width as usize,
+                height: height as usize
+            }),
+            _ => return Err(usage(format!("--viewport expects X,Y,W,H with a positive size, got '{}'", value)))
+        },
+        None => None
+    };
+
+    Ok(RunOptions {
+        pattern: args.positional[0].clone(),
+        generations,
+        rule: rule_option(&args)?,
+        backend: backend_option(&args)?,
+        topology: topology_option(&args)?,
+        step_log2,
+        viewport
+    })
+}
+
+fn run_pattern(args: &[String]) -> Result<(), CliError> {
+    let options = parse_run_options(args)?;
+    let pattern = load_pattern(&options.pattern)?;
+    let rule = options.rule.resolve(&pattern)?;
+
+    let viewport = options.viewport.unwrap_or_else(|| {
+        bounding_box(&pattern.cells)
+            .and_then(|(top_left, bottom_right)| Viewport::covering(top_left, bottom_right))
+            .unwrap_or(Viewport { top: 0, left: 0, width: 20, height: 20 })
+    });
+
+    let render = match &rule {
+        AnyRule::Life(rule) if rule.neighborhood() == Neighborhood::Hexagonal => render_hex_states_text,
+        AnyRule::Table(table) if table.neighborhood() == Neighborhood::Hexagonal => render_hex_states_text,
+        _ => render_states_text
+    };
+
+    let iter = GOLGenerationIterator::with_states(pattern.into_states(), rule, options.topology, options.backend)
+        .map_err(engine_error)?
+        .with_step_pow2(options.step_log2);
+
+    // The seed is generation 0 so one more than asked for is taken
+    for (index, states) in iter.into_states().take(options.generations.saturating_add(1)).enumerate() {
+        let generation = (index as u128) << options.step_log2;
+        let population = states.values().filter(|&&state| state == 1).count();
+        println!("generation {}, population {}", generation, population);
+        println!("{}", render(&states, &viewport));
+    }
+
+    Ok(())
+}
+
+fn tui(args: &[String]) -> Result<(), CliError> {
+    let args = Arguments::parse(args, &["rule", "rule-file", "torus", "backend", "threads"])?;
+    args.expect_positional(&["<pattern>"])?;
+
+    let backend = backend_option(&args)?;
+    let pattern = load_pattern(&args.positional[0])?;
+    let rule = rule_option(&args)?.resolve(&pattern)?;
+    let topology = topology_option(&args)?;
+
+    let iter = GOLGenerationIterator::with_states(pattern.into_states(), rule, topology, backend)
+        .map_err(engine_error)?;
+
+    Terminal::open()
+        .and_then(|mut terminal| Viewer::new(iter).run(&mut terminal))
+        .map_err(|err| CliError::Failed(format!("terminal: {}", err)))
+}
+
+fn edit(args: &[String]) -> Result<(), CliError> {
+    let args = Arguments::parse(args, &["rule", "rule-file"])?;
+    if args.positional.len() > 1 {
+        return Err(usage("expected at most one [pattern]"));
+    }
+
+    // A file that doesn't exist yet is where the new pattern gets saved
+    let path = args.positional.first().map(PathBuf::from);
+    let pattern = match &path {
+        Some(path) if path.exists() => load_pattern(&path.to_string_lossy())?,
+        _ => Pattern::new(vec![])
+    };
+    let rule = rule_option(&args)?.resolve(&pattern)?;
+
+    Terminal::open()
+        .and_then(|mut terminal| Editor::new(pattern, rule, path).run(&mut terminal))
+        .map_err(|err| CliError::Failed(format!("terminal: {}", err)))
+}
+
+fn soup(args: &[String]) -> Result<(), CliError> {
+    let args = Arguments::parse(args, &["size", "density", "symmetry"])?;
+    args.expect_positional(&["<seed>", "<output>"])?;
+
+    let soup = soup_option(&args)?;
+
+    let seed = &args.positional[0];
+    let output = Path::new(&args.positional[1]);
+    let format = Format::from_path(output).ok_or_else(|| {
+        usage(format!("can't tell the format of '{}' from its extension", output.display()))
+    })?;
+
+    let mut pattern: Pattern<CellCoordinate> = Pattern::new(soup.generate(seed));
+    pattern.name = Some(format!("soup {}", seed));
+    fs::write(output, pattern::write(&pattern, format))
+        .map_err(|err| CliError::Failed(format!("{}: {}", output.display(), err)))
+}
+
+fn census(args: &[String]) -> Result<(), CliError> {
+    let args = Arguments::parse(
+        args,
+        &["soups", "prefix", "size", "density", "symmetry", "rule", "threads", "max-generations", "rare"]
+    )?;
+    args.expect_positional(&[])?;
+
+    let count = |name: &str, default: u64| -> Result<u64, CliError> {
+        match args.option(name) {
+            Some(value) => value.parse().map_err(|_| usage(format!("invalid --{} '{}'", name, value))),
+            None => Ok(default)
+        }
+    };
+
+    let soups = count("soups", 1000)?;
+    let max_generations = count("max-generations", 20_000)?;
+    let rare = count("rare", 1)?;
+    let threads = threads_option(&args)?;
+
+    let rule: Rule = match args.option("rule") {
+        Some(value) => value.parse().map_err(|err| usage(format!("invalid --rule '{}', {}", value, err)))?,
+        None => Rule::default()
+    };
+
+    let search = Search { soup: soup_option(&args)?, rule, max_generations };
+    let census = search.run(args.option("prefix").unwrap_or("soup_"), soups, threads);
+
+    println!("{} soups, {} unsettled", census.soups(), census.unsettled().len());
+    println!();
+    println!("{:>10}  {:<32}  first seen in", "count", "object");
+    for (code, count, seed) in census.objects() {
+        println!("{:>10}  {:<32}  {}", count, code, seed);
+    }
+
+    let rare = census.rare(rare);
+    if !rare.is_empty() {
+        println!();
+        println!("rare finds:");
+        for (code, count, seed) in rare {
+            println!("    {} x{} in {}", code, count, seed);
+        }
+    }
+
+    if !census.unsettled().is_empty() {
+        println!();
+        println!("unsettled: {}", census.unsettled().join(" "));
+    }
+
+    Ok(())
+}
+
+fn analyze(args: &[String]) -> Result<(), CliError> {
+    let args = Arguments::parse(args, &["rule", "rule-file", "torus", "max-generations"])?;
+    args.expect_positional(&["<pattern>"])?;
+
+    let max_generations: u64 = match args.option("max-generations") {
+        Some(value) => value.parse().map_err(|_| usage(format!("invalid --max-generations '{}'", value)))?,
+        None => 10_000
+    };
+
+    let pattern = load_pattern(&args.positional[0])?;
+    let rule = rule_option(&args)?.resolve(&pattern)?;
+    let topology = topology_option(&args)?;
+
+    let seed = pattern.into_states();
+    let generations = |seed| {
+        GOLGenerationIterator::with_states(seed, rule.clone(), topology, Backend::HashSet).map_err(engine_error)
+    };
+    let outcome = analysis::classify_states(generations(seed.clone())?.into_states(), max_generations);
+    println!("{}", outcome);
+
+    // apgcodes only describe live cells
+    if let AnyRule::Wireworld | AnyRule::Table(_) = rule {
+        return Ok(());
+    }
+
+    // Run again up to the repeating phases to name them
+    let (prefix, generation, period) = match outcome {
+        Outcome::StillLife { generation } => (Prefix::StillLife, generation, 1),
+        Outcome::Oscillator { period, generation } => (Prefix::Oscillator(period), generation, period),
+        Outcome::Spaceship { period, generation, .. } => (Prefix::Spaceship(period), generation, period),
+        _ => return Ok(())
+    };
+
+    let phases: Vec<_> = generations(seed)?
+        .skip(generation as usize)
+        .take(period as usize)
+        .collect();
+    println!("apgcode: {}", apgcode::encode(prefix, &phases));
+
+    Ok(())
+}
+
+fn convert(args: &[String]) -> Result<(), CliError> {
+    let args = Arguments::parse(args, &[])?;
+    args.expect_positional(&["<input>", "<output>"])?;
+
+    let output = Path::new(&args.positional[1]);
+    let format = Format::from_path(output).ok_or_else(|| {
+        usage(format!("can't tell the format of '{}' from its extension", output.display()))
+    })?;
+
+    let pattern = load_pattern(&args.positional[0])?;
+    fs::write(output, pattern::write(&pattern, format))
+        .map_err(|err| CliError::Failed(format!("{}: {}", output.display(), err)))
+}
+
+fn info(args: &[String]) -> Result<(), CliError> {
+    let args = Arguments::parse(args, &[])?;
+    args.expect_positional(&["<pattern>"])?;
+
+    let mut pattern = load_pattern(&args.positional[0])?;
+    pattern.cells.sort_unstable();
+    pattern.cells.dedup();
+
+    if let Some(name) = &pattern.name {
+        println!("name: {}", name);
+    }
+    if let Some(author) = &pattern.author {
+        println!("author: {}", author);
+    }
+    println!("rule: {}", pattern.rule.clone().unwrap_or_default());
+    println!("population: {}", pattern.cells.len());
+
+    match bounding_box(&pattern.cells) {
+        Some(((top, left), (bottom, right))) => println!(
+            "bounding box: x {}..{}, y {}..{} ({}x{})",
+            left,
+            right,
+            top,
+            bottom,
+            right as i128 - left as i128 + 1,
+            bottom as i128 - top as i128 + 1
+        ),
+        None => println!("bounding box: empty")
+    }
+
+    Ok(())
+}
+
+fn load_pattern(path: &str) -> Result<Pattern<CellCoordinate>, CliError> {
+    let path = Path::new(path);
+    let content = fs::read_to_string(path).map_err(|err| CliError::Failed(format!("{}: {}", path.display(), err)))?;
+    let format = Format::detect(Some(path), &content);
+
+    pattern::parse(&content, format).map_err(|err| CliError::Failed(format!("{}: {}", path.display(), err)))
+}
+
+/// `--rule` or `--rule-file`, the file isn't read until the rule is resolved.
+#[derive(Debug, Clone, PartialEq)]
+enum RuleOption {
+    FromPattern,
+    Rule(AnyRule),
+    File(String)
+}
+
+impl RuleOption {
+    /// The rule given, the table in the rule file, otherwise whatever rule the pattern file asked
+    /// for.
+    fn resolve(self, pattern: &Pattern<CellCoordinate>) -> Result<AnyRule, CliError> {
+        match self {
+            RuleOption::FromPattern => Ok(pattern.rule.clone().unwrap_or_default()),
+            RuleOption::Rule(rule) => Ok(rule),
+            RuleOption::File(path) => {
+                let content = fs::read_to_string(&path).map_err(|err| CliError::Failed(format!("{}: {}", path, err)))?;
+                let table: Table = content.parse().map_err(|err| CliError::Failed(format!("{}: {}", path, err)))?;
+                Ok(table.into())
+            }
+        }
+    }
+}
+
+fn rule_option(args: &Arguments) -> Result<RuleOption, CliError> {
+    match (args.option("rule"), args.option("rule-file")) {
+        (Some(_), Some(_)) => Err(usage("give either --rule or --rule-file, not both")),
+        (Some(value), None) => value
+            .parse()
+            .map(RuleOption::Rule)
+            .map_err(|err| usage(format!("invalid --rule '{}', {}", value, err))),
+        (None, Some(path)) => Ok(RuleOption::File(path.to_string())),
+        (None, None) => Ok(RuleOption::FromPattern)
+    }
+}
+
+/// Backends that can't run the rule or topology asked for are a usage error, anything about the
+/// pattern itself is not.
+fn engine_error(err: EngineError) -> CliError {
+    match err {
+        EngineError::UnsupportedTopology(_)
+        | EngineError::UnsupportedRule(..)
+        | EngineError::PlaneOnly(_)
+        | EngineError::DyingStates(..) => usage(err.to_string()),
+        EngineError::UnknownRule(_) | EngineError::OutOfRange(_) | EngineError::InvalidState(..) => {
+            CliError::Failed(err.to_string())
+        }
+    }
+}
+
+fn backend_option(args: &Arguments) -> Result<Backend, CliError> {
+    if args.option("threads").is_some() && args.option("backend") != Some("parallel") {
+        return Err(usage("--threads only applies to --backend parallel"));
+    }
+
+    match args.option("backend") {
+        Some("hashset") | None => Ok(Backend::HashSet),
+        Some("hashlife") => Ok(Backend::HashLife),
+        Some("tiled") => Ok(Backend::Tiled),
+        Some("parallel") => Ok(Backend::Parallel { threads: threads_option(args)? }),
+        Some(value) => Err(usage(format!("unknown --backend '{}'", value)))
+    }
+}
+
+/// `--threads`, as many as the machine has by default.
+fn threads_option(args: &Arguments) -> Result<usize, CliError> {
+    match args.option("threads") {
+        Some(value) => value
+            .parse()
+            .ok()
+            .filter(|threads| *threads > 0)
+            .ok_or_else(|| usage(format!("--threads must be a positive number, got '{}'", value))),
+        None => Ok(thread::available_parallelism().map_or(1, |threads| threads.get()))
+    }
+}
+
+/// `--size`, `--density` and `--symmetry`, 16x16 at 0.5 without symmetry by default.
+fn soup_option(args: &Arguments) -> Result<Soup, CliError> {
+    let (width, height) = match args.option("size") {
+        Some(value) => match parse_numbers(value)?[..] {
+            [width, height] if width >= 0 && height >= 0 => (width as usize, height as usize),
+            _ => return Err(usage(format!("--size expects W,H, got '{}'", value)))
+        },
+        None => (16, 16)
+    };
+
+    let density: f64 = match args.option("density") {
+        Some(value) => value.parse().map_err(|_| usage(format!("invalid --density '{}'", value)))?,
+        None => 0.5
+    };
+
+    let symmetry: Symmetry = match args.option("symmetry") {
+        Some(value) => value.parse().map_err(|err| usage(format!("invalid --symmetry, {}", err)))?,
+        None => Symmetry::C1
+    };
+
+    Soup::new(width, height, density, symmetry).map_err(|err| usage(err.to_string()))
+}
+
+fn topology_option(args: &Arguments) -> Result<Topology, CliError> {
+    let value = match args.option("torus") {
+        Some(value) => value,
+        None => return Ok(Topology::Plane)
+    };
+
+    match parse_numbers(value)?[..] {
+        [width, height] => Topology::torus(width, height)
+            .ok_or_else(|| usage(format!("--torus size must be positive, got '{}'", value))),
+        _ => Err(usage(format!("--torus expects W,H, got '{}'", value)))
+    }
+}
+
+/// Comma separated list of coordinates such as `-10,-10,20,20`.
+fn parse_numbers(value: &str) -> Result<Vec<CellCoordinate>, CliError> {
+    value
+        .split(',')
+        .map(|number| number.trim().parse().map_err(|_| usage(format!("'{}' is not a number", number))))
+        .collect()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    /// Writes `content` to a file of its own in the temp directory.
+    fn temp_file(name: &str, content: &str) -> String {
+        let path = std::env::temp_dir().join(format!("rust_conway_gol_cli_{}_{}", std::process::id(), name));
+        fs::write(&path, content).unwrap();
+        path.to_string_lossy().into_owned()
+    }
+
+    fn exit_code(arguments: &[&str]) -> i32 {
+        run(&args(arguments)).err().map_or(0, |err| err.exit_code())
+    }
+
+    #[test]
+    fn run_defaults() {
+        let options = parse_run_options(&args(&["glider.rle"])).unwrap();
+        assert_eq!(
+            options,
+            RunOptions {
+                pattern: "glider.rle".to_string(),
+                generations: 10,
+                rule: RuleOption::FromPattern,
+                backend: Backend::HashSet,
+                topology: Topology::Plane,
+                step_log2: 0,
+                viewport: None
+            }
+        );
+    }
+
+    #[test]
+    fn run_options() {
+        let options = parse_run_options(&args(&[
+            "--generations", "18446744073709551615", "glider.rle", "--rule", "B36/S23", "--torus", "20,10",
+            "--backend", "parallel", "--threads", "3", "--step-pow2", "5", "--viewport", "-5,-4,10,8",
+            "--generations", "7"
+        ]))
+        .unwrap();
+        assert_eq!(
+            options,
+            RunOptions {
+                pattern: "glider.rle".to_string(),
+                generations: 7,
+                rule: RuleOption::Rule("B36/S23".parse().unwrap()),
+                backend: Backend::Parallel { threads: 3 },
+                topology: Topology::torus(20, 10).unwrap(),
+                step_log2: 5,
+                viewport: Some(Viewport { top: -4, left: -5, width: 10, height: 8 })
+            }
+        );
+
+        let options = parse_run_options(&args(&["glider.rle", "--generations", "18446744073709551615"])).unwrap();
+        assert_eq!(options.generations, usize::MAX);
+        let options = parse_run_options(&args(&["glider.rle", "--rule-file", "Langtons-Loops.rule"])).unwrap();
+        assert_eq!(options.rule, RuleOption::File("Langtons-Loops.rule".to_string()));
+    }
+
+    #[test]
+    fn run_usage_errors() {
+        let usage_errors: [&[&str]; 10] = [
+            &[],
+            &["glider.rle", "other.rle"],
+            &["glider.rle", "--generations", "-1"],
+            &["glider.rle", "--generations"],
+            &["glider.rle", "--frames", "3"],
+            &["glider.rle", "--threads", "3"],
+            &["glider.rle", "--backend", "quantum"],
+            &["glider.rle", "--rule", "B3/S23", "--rule-file", "life.rule"],
+            &["glider.rle", "--step-pow2", "64"],
+            &["glider.rle", "--viewport", "0,0,0,10"]
+        ];
+        for arguments in usage_errors.iter() {
+            match parse_run_options(&args(arguments)) {
+                Err(CliError::Usage(_)) => {}
+                other => panic!("{:?} gave {:?}", arguments, other)
+            }
+        }
+    }
+
+    #[test]
+    fn exit_codes() {
+        assert_eq!(exit_code(&[]), 2);
+        assert_eq!(exit_code(&["frobnicate"]), 2);
+        assert_eq!(exit_code(&["help"]), 0);
+
+        let glider = temp_file("glider.rle", "x = 3, y = 3, rule = B3/S23\nbo$2bo$3o!\n");
+        assert_eq!(exit_code(&["info", &glider]), 0);
+        assert_eq!(exit_code(&["run", &glider, "--generations", "2"]), 0);
+        assert_eq!(exit_code(&["run", &glider, "--backend", "hashlife", "--torus", "8,8"]), 2);
+        assert_eq!(exit_code(&["run", &glider, "--rule", "B2/S/C3", "--backend", "tiled"]), 2);
+        assert_eq!(exit_code(&["run", "/nonexistent/glider.rle"]), 1);
+
+        // Patterns asking for something the engines can't give aren't a usage error
+        let loops = temp_file("loops.rle", "x = 1, y = 1, rule = Langtons-Loops\nA!\n");
+        assert_eq!(exit_code(&["run", &loops]), 1);
+        let dying = temp_file("dying.rle", "x = 3, y = 1, rule = B2/S/C3\nAyO!\n");
+        assert_eq!(exit_code(&["run", &dying]), 1);
+        assert_eq!(exit_code(&["analyze", &dying]), 1);
+
+        assert_eq!(engine_error(EngineError::OutOfRange(Backend::HashLife)).exit_code(), 1);
+        assert_eq!(engine_error(EngineError::UnsupportedTopology(Backend::Tiled)).exit_code(), 2);
+
+        for path in [glider, loops, dying] {
+            fs::remove_file(path).unwrap();
+        }
+    }
+}
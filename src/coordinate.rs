@@ -0,0 +1,66 @@
+use std::fmt::{Debug, Display};
+use std::hash::Hash;
+use std::ops::{Add, Sub};
+
+/// Integer type a cell's row and column are stored as.
+///
+/// Implemented for the signed primitives so the same engine can trade memory for range, `i32`
+/// for compact universes up to `i128` for patterns that keep expanding over very long runs.
+pub trait Coordinate:
+    Copy + Eq + Ord + Hash + Debug + Display + Send + Sync + 'static
+    + Add<Output = Self> + Sub<Output = Self>
+{
+    const MIN: Self;
+    const MAX: Self;
+    const ZERO: Self;
+    const ONE: Self;
+
+    fn checked_add(self, rhs: Self) -> Option<Self>;
+    fn checked_sub(self, rhs: Self) -> Option<Self>;
+    fn rem_euclid(self, rhs: Self) -> Self;
+
+    /// Every implementor fits in an i128, handy for arithmetic that could overflow Self.
+    fn to_i128(self) -> i128;
+
+    /// None if `value` is outside the range of Self.
+    fn from_i128(value: i128) -> Option<Self>;
+}
+
+macro_rules! impl_coordinate {
+    ($($t:ty),*) => {
+        $(
+            impl Coordinate for $t {
+                const MIN: $t = <$t>::MIN;
+                const MAX: $t = <$t>::MAX;
+                const ZERO: $t = 0;
+                const ONE: $t = 1;
+
+                fn checked_add(self, rhs: $t) -> Option<$t> {
+                    <$t>::checked_add(self, rhs)
+                }
+
+                fn checked_sub(self, rhs: $t) -> Option<$t> {
+                    <$t>::checked_sub(self, rhs)
+                }
+
+                fn rem_euclid(self, rhs: $t) -> $t {
+                    <$t>::rem_euclid(self, rhs)
+                }
+
+                fn to_i128(self) -> i128 {
+                    self as i128
+                }
+
+                fn from_i128(value: i128) -> Option<$t> {
+                    if value < <$t>::MIN as i128 || value > <$t>::MAX as i128 {
+                        return None;
+                    }
+
+                    Some(value as $t)
+                }
+            }
+        )*
+    };
+}
+
+impl_coordinate!(i8, i16, i32, i64, i128, isize);
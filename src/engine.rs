@@ -0,0 +1,467 @@
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::fmt;
+
+use crate::automaton::{StateEngine, Wireworld};
+use crate::coordinate::Coordinate;
+use crate::hashlife::HashLife;
+use crate::ltl::LtlEngine;
+use crate::rule::{AnyRule, Neighborhood, Rule};
+use crate::tiled::TiledEngine;
+use crate::topology::Topology;
+use crate::{compute_next_gen_parallel, Cell};
+
+/// Largest k `Engine::step_pow2` takes. HashLife's root can't grow past level 127 and jumping
+/// 2^k generations needs a root of level k + 3.
+pub const MAX_STEP_POW2: u32 = 124;
+
+/// A way of computing generations. `GOLGenerationIterator` drives one of these so backends can be
+/// swapped without changing how generations are consumed.
+pub trait Engine<C: Coordinate> {
+    /// Live cells of the current generation.
+    fn cells(&self) -> HashSet<Cell<C>>;
+
+    /// State of every cell that isn't dead, 1 being alive. Only Generations rules have other
+    /// states so by default this is just the live cells.
+    fn states(&self) -> HashMap<Cell<C>, u8> {
+        self.cells().into_iter().map(|cell| (cell, 1)).collect()
+    }
+
+    /// Number of live cells, engines that can count without building the HashSet override this.
+    fn population(&self) -> u128 {
+        self.cells().len() as u128
+    }
+
+    /// Moves forward a single generation.
+    fn step(&mut self);
+
+    /// Moves forward 2^k generations, engines that can jump ahead faster than stepping override
+    /// this. Panics if k is over `MAX_STEP_POW2`.
+    fn step_pow2(&mut self, k: u32) {
+        assert!(k <= MAX_STEP_POW2, "step_pow2 can jump at most 2^{} generations, got 2^{}", MAX_STEP_POW2, k);
+        for _ in 0..(1u128 << k) {
+            self.step();
+        }
+    }
+
+    /// Hands back the current generation then moves forward 2^k generations. Engines that already
+    /// hold the generation as a HashSet override this to avoid a copy.
+    fn advance(&mut self, k: u32) -> HashSet<Cell<C>> {
+        let cells = self.cells();
+        self.step_pow2(k);
+        cells
+    }
+}
+
+/// The engines `GOLGenerationIterator` can be built with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Backend {
+    /// One HashSet of live cells swept every generation, supports every topology. Larger than
+    /// Life rules get their own engine here, which only runs on the plane, and Wireworld and rule
+    /// tables run on `automaton::StateEngine`.
+    #[default]
+    HashSet,
+    /// Memoized quadtree that can jump 2^k generations at once, plane only.
+    HashLife,
+    /// 64x64 bitboard tiles for dense regions, plane only and totalistic Moore rules only.
+    Tiled,
+    /// The HashSet engine with each step split across `threads` threads, supports every topology.
+    Parallel { threads: usize }
+}
+
+impl Backend {
+    pub fn create<C: Coordinate>(
+        &self,
+        seed: Vec<Cell<C>>,
+        rule: impl Into<AnyRule>,
+        topology: Topology<C>
+    ) -> Result<Box<dyn Engine<C>>, EngineError> {
+        let rule = match rule.into() {
+            AnyRule::Life(rule) => rule,
+            AnyRule::LargerThanLife(rule) => {
+                return match self {
+                    Backend::HashSet if topology == Topology::Plane => Ok(Box::new(LtlEngine::new(seed, rule))),
+                    Backend::HashSet => Err(EngineError::PlaneOnly(rule.into())),
+                    _ => Err(EngineError::UnsupportedRule(*self, rule.into()))
+                };
+            }
+            rule => return self.create_with_states(seed.into_iter().map(|cell| (cell, 1)).collect(), rule, topology)
+        };
+
+        match self {
+            Backend::HashSet => Ok(Box::new(HashSetEngine::new(seed, rule, topology))),
+            Backend::Parallel { threads } => {
+                Ok(Box::new(HashSetEngine::new(seed, rule, topology).with_threads(*threads)))
+            }
+            Backend::HashLife => {
+                self.check_two_state_plane(rule, topology)?;
+                Ok(Box::new(HashLife::new(seed, rule)?))
+            }
+            Backend::Tiled => {
+                self.check_two_state_plane(rule, topology)?;
+                // Its bitwise counters only know how many of the 8 neighbors are alive, not which
+                if !rule.is_totalistic() || rule.neighborhood() != Neighborhood::Moore {
+                    return Err(EngineError::UnsupportedRule(*self, rule.into()));
+                }
+                Ok(Box::new(TiledEngine::new(seed, rule)))
+            }
+        }
+    }
+
+    /// Like `create` starting from the state of each cell. Rules other than Wireworld and rule
+    /// tables can only start from live cells unless they are Generations rules on the HashSet
+    /// backend.
+    pub fn create_with_states<C: Coordinate>(
+        &self,
+        seed: HashMap<Cell<C>, u8>,
+        rule: impl Into<AnyRule>,
+        topology: Topology<C>
+    ) -> Result<Box<dyn Engine<C>>, EngineError> {
+        let dying = seed.values().any(|&state| state > 1);
+        match rule.into() {
+            AnyRule::Named(name) => Err(EngineError::UnknownRule(name)),
+            rule @ AnyRule::Wireworld | rule @ AnyRule::Table(_) if *self != Backend::HashSet => {
+                Err(EngineError::UnsupportedRule(*self, rule))
+            }
+            AnyRule::Wireworld => Ok(Box::new(StateEngine::new(seed, Wireworld, topology)?)),
+            AnyRule::Table(table) => Ok(Box::new(StateEngine::new(seed, table, topology)?)),
+            // The HashSet engine has no way to start with cells already dying
+            AnyRule::Life(rule) if dying && *self == Backend::HashSet => {
+                Ok(Box::new(StateEngine::new(seed, rule, topology)?))
+            }
+            rule if dying => Err(EngineError::DyingStates(*self, rule)),
+            rule => self.create(seed.into_keys().collect(), rule, topology)
+        }
+    }
+
+    fn check_two_state_plane<C: Coordinate>(&self, rule: Rule, topology: Topology<C>) -> Result<(), EngineError> {
+        if topology != Topology::Plane {
+            return Err(EngineError::UnsupportedTopology(*self));
+        }
+        if rule.states() > 2 {
+            return Err(EngineError::UnsupportedRule(*self, rule.into()));
+        }
+
+        Ok(())
+    }
+}
+
+impl fmt::Display for Backend {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Backend::HashSet => write!(f, "hashset"),
+            Backend::HashLife => write!(f, "hashlife"),
+            Backend::Tiled => write!(f, "tiled"),
+            Backend::Parallel { threads } => write!(f, "parallel ({} threads)", threads)
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EngineError {
+    /// The backend can't run on the requested topology.
+    UnsupportedTopology(Backend),
+    /// The backend can't run the rule, such as Generations rules on HashLife.
+    UnsupportedRule(Backend, AnyRule),
+    /// The rule can only be run on the plane, whatever the backend.
+    PlaneOnly(AnyRule),
+    /// The backend can't start the rule with cells in states other than alive.
+    DyingStates(Backend, AnyRule),
+    /// A rule only known by name, its rule table has to be loaded to run it.
+    UnknownRule(String),
+    /// The seed has cells further from the origin than the backend can hold.
+    OutOfRange(Backend),
+    /// The seed has a cell in a state the rule doesn't have, holds the state and how many states
+    /// the rule has.
+    InvalidState(u8, u16)
+}
+
+impl fmt::Display for EngineError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            EngineError::UnsupportedTopology(backend) => {
+                write!(f, "the {} backend only supports the plane topology", backend)
+            }
+            EngineError::UnsupportedRule(backend, rule) => {
+                write!(f, "the {} backend doesn't support {}", backend, rule)
+            }
+            EngineError::PlaneOnly(rule) => write!(f, "{} only runs on the plane topology", rule),
+            EngineError::DyingStates(backend, rule) => {
+                write!(f, "the {} backend can't start {} with cells in states other than alive", backend, rule)
+            }
+            EngineError::UnknownRule(name) => write!(f, "'{}' isn't a built in rule, its rule table has to be loaded", name),
+            EngineError::OutOfRange(backend) => write!(f, "the seed is outside the range the {} backend covers", backend),
+            EngineError::InvalidState(state, states) => {
+                write!(f, "the seed has a cell in state {} but the rule only has {} states", state, states)
+            }
+        }
+    }
+}
+
+impl Error for EngineError {}
+
+/// State a cell in `state` moves to when it doesn't stay alive under a rule with `states` states,
+/// 0 once it has run out of dying states.
+pub(crate) fn decay(states: u8, state: u8) -> u8 {
+    if state >= states - 1 {
+        0
+    } else {
+        state + 1
+    }
+}
+
+/// The live cells of a generation along with the cells in the dying states of rules with more
+/// than 2 states, for engines that compute the live cells and leave the dying ones to this.
+pub(crate) struct Generation<C: Coordinate> {
+    live: HashSet<Cell<C>>,
+    dying: HashMap<Cell<C>, u8>,
+    states: u8
+}
+
+impl<C: Coordinate> Generation<C> {
+    pub(crate) fn new(live: HashSet<Cell<C>>, states: u8) -> Generation<C> {
+        Generation {
+            live,
+            dying: HashMap::new(),
+            states
+        }
+    }
+
+    pub(crate) fn live(&self) -> &HashSet<Cell<C>> {
+        &self.live
+    }
+
+    /// State of every cell that isn't dead, as `Engine::states` hands them back.
+    pub(crate) fn states(&self) -> HashMap<Cell<C>, u8> {
+        let mut states = self.dying.clone();
+        states.extend(self.live.iter().map(|&cell| (cell, 1)));
+        states
+    }
+
+    /// Moves on to `next_gen` and hands back the live cells it replaces. Live cells that didn't
+    /// survive start dying and dying cells can't be born into.
+    pub(crate) fn replace(&mut self, mut next_gen: HashSet<Cell<C>>) -> HashSet<Cell<C>> {
+        if self.states > 2 {
+            next_gen.retain(|cell| !self.dying.contains_key(cell));
+
+            let states = self.states;
+            let mut dying: HashMap<Cell<C>, u8> = self
+                .dying
+                .drain()
+                .map(|(cell, state)| (cell, decay(states, state)))
+                .filter(|&(_, state)| state != 0)
+                .collect();
+            dying.extend(self.live.difference(&next_gen).map(|&cell| (cell, decay(states, 1))));
+            self.dying = dying;
+        }
+
+        std::mem::replace(&mut self.live, next_gen)
+    }
+}
+
+/// `Engine::advance` for engines that keep their cells in a `Generation`, the live cells of the
+/// first step are handed back rather than copied. `next_gen` works out the generation after the
+/// one `generation` gives.
+pub(crate) fn advance_generation<C: Coordinate, E>(
+    engine: &mut E,
+    k: u32,
+    generation: fn(&mut E) -> &mut Generation<C>,
+    next_gen: fn(&E) -> HashSet<Cell<C>>
+) -> HashSet<Cell<C>> {
+    assert!(k <= MAX_STEP_POW2, "step_pow2 can jump at most 2^{} generations, got 2^{}", MAX_STEP_POW2, k);
+
+    let next = next_gen(engine);
+    let current_gen = generation(engine).replace(next);
+
+    for _ in 1..(1u128 << k) {
+        let next = next_gen(engine);
+        generation(engine).replace(next);
+    }
+
+    current_gen
+}
+
+/// The original engine, a HashSet of live cells where every step sweeps the live cells and their
+/// neighbors.
+pub struct HashSetEngine<C: Coordinate> {
+    current_gen: Generation<C>,
+    rule: Rule,
+    topology: Topology<C>,
+    threads: usize
+}
+
+impl<C: Coordinate> HashSetEngine<C> {
+    /// Seed cells outside of a torus board are wrapped onto it.
+    pub fn new(seed: Vec<Cell<C>>, rule: Rule, topology: Topology<C>) -> HashSetEngine<C> {
+        let gen_zero = seed.into_iter().map(|cell| topology.wrap(cell)).collect();
+        HashSetEngine {
+            current_gen: Generation::new(gen_zero, rule.states()),
+            rule,
+            topology,
+            threads: 1
+        }
+    }
+
+    /// Splits every step across `threads` threads, the generations produced don't change.
+    pub fn with_threads(mut self, threads: usize) -> HashSetEngine<C> {
+        self.threads = threads.max(1);
+        self
+    }
+
+    fn next_gen(&self) -> HashSet<Cell<C>> {
+        compute_next_gen_parallel(self.current_gen.live(), &self.rule, &self.topology, self.threads)
+    }
+}
+
+impl<C: Coordinate> Engine<C> for HashSetEngine<C> {
+    fn cells(&self) -> HashSet<Cell<C>> {
+        self.current_gen.live().clone()
+    }
+
+    fn states(&self) -> HashMap<Cell<C>, u8> {
+        self.current_gen.states()
+    }
+
+    fn population(&self) -> u128 {
+        self.current_gen.live().len() as u128
+    }
+
+    fn step(&mut self) {
+        let next_gen = self.next_gen();
+        self.current_gen.replace(next_gen);
+    }
+
+    fn advance(&mut self, k: u32) -> HashSet<Cell<C>> {
+        advance_generation(self, k, |engine| &mut engine.current_gen, HashSetEngine::next_gen)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::soup;
+    use crate::NEIGHBOR_OFFSETS;
+
+    /// Next generation of a Generations rule with only B and S counts, worked out cell by cell.
+    fn brute_force(current: &HashMap<Cell, u8>, rule: &Rule) -> HashMap<Cell, u8> {
+        let candidates: HashSet<Cell> = current
+            .keys()
+            .flat_map(|&(row, col)| NEIGHBOR_OFFSETS.iter().map(move |&(dr, dc)| (row + dr as i64, col + dc as i64)))
+            .chain(current.keys().copied())
+            .collect();
+
+        candidates
+            .into_iter()
+            .filter_map(|(row, col)| {
+                let alive = NEIGHBOR_OFFSETS
+                    .iter()
+                    .filter(|&&(dr, dc)| current.get(&(row + dr as i64, col + dc as i64)) == Some(&1))
+                    .count() as u8;
+                let state = match current.get(&(row, col)).copied().unwrap_or(0) {
+                    0 if rule.is_born(alive) => 1,
+                    0 => 0,
+                    1 if rule.survives(alive) => 1,
+                    state => rule.decay(state)
+                };
+                Some(((row, col), state)).filter(|&(_, state)| state != 0)
+            })
+            .collect()
+    }
+
+    /// Next generation of a totalistic two state rule counting only the neighbors at `offsets`.
+    fn brute_force_neighborhood(current: &HashSet<Cell>, rule: &Rule, offsets: &[(i64, i64)]) -> HashSet<Cell> {
+        let candidates: HashSet<Cell> = current
+            .iter()
+            .flat_map(|&(row, col)| offsets.iter().map(move |&(dr, dc)| (row - dr, col - dc)))
+            .chain(current.iter().copied())
+            .collect();
+
+        candidates
+            .into_iter()
+            .filter(|&(row, col)| {
+                let alive = offsets.iter().filter(|&&(dr, dc)| current.contains(&(row + dr, col + dc))).count() as u8;
+                if current.contains(&(row, col)) {
+                    rule.survives(alive)
+                } else {
+                    rule.is_born(alive)
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn von_neumann_and_hexagonal_neighborhoods() {
+        let von_neumann = [(-1, 0), (0, -1), (0, 1), (1, 0)];
+        // Every Moore neighbor but the top right and bottom left
+        let hexagonal = [(-1, -1), (-1, 0), (0, -1), (0, 1), (1, 0), (1, 1)];
+
+        for &(rule, offsets) in &[("B2/S1V", &von_neumann[..]), ("B2/S12H", &hexagonal[..])] {
+            let rule: Rule = rule.parse().unwrap();
+            for &backend in &[Backend::HashSet, Backend::HashLife] {
+                let mut engine = backend.create(soup(5, 16), rule, Topology::Plane).unwrap();
+                let mut expected: HashSet<Cell> = soup(5, 16).into_iter().collect();
+
+                for generation in 0..30 {
+                    assert_eq!(engine.cells(), expected, "{} on {:?} at generation {}", rule, backend, generation);
+                    engine.step();
+                    expected = brute_force_neighborhood(&expected, &rule, offsets);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn brians_brain_dying_cells() {
+        let rule: Rule = "B2/S/C3".parse().unwrap();
+        let mut engine = Backend::HashSet.create(soup(5, 16), rule, Topology::Plane).unwrap();
+        let mut expected: HashMap<Cell, u8> = soup(5, 16).into_iter().map(|cell| (cell, 1)).collect();
+
+        for generation in 0..40 {
+            assert_eq!(engine.states(), expected, "generation {}", generation);
+            assert_eq!(engine.population(), expected.values().filter(|&&state| state == 1).count() as u128);
+            engine.step();
+            expected = brute_force(&expected, &rule);
+        }
+    }
+
+    #[test]
+    fn dying_cells_are_not_neighbors_or_born_into() {
+        let rule: Rule = "B2/S/C3".parse().unwrap();
+        // Two live cells start dying and give birth above and below them
+        let mut engine = Backend::HashSet.create(vec![(0, 0), (0, 1)], rule, Topology::Plane).unwrap();
+        engine.step();
+        let mut states = HashMap::new();
+        states.extend([(0, 0), (0, 1)].iter().map(|&cell| (cell, 2)));
+        states.extend([(-1, 0), (-1, 1), (1, 0), (1, 1)].iter().map(|&cell| (cell, 1)));
+        assert_eq!(engine.states(), states);
+
+        // Had the dying cells counted as neighbors (-1, 0) and (-1, 2) would be born, and (0, 1) and
+        // (1, 1) have 2 live neighbors but are dying
+        let seed = vec![((0, 0), 1), ((0, 2), 1), ((0, 1), 2), ((1, 1), 2)].into_iter().collect();
+        let mut engine = Backend::HashSet.create_with_states(seed, rule, Topology::Plane).unwrap();
+        engine.step();
+        let mut states = HashMap::new();
+        states.extend([(0, 0), (0, 2)].iter().map(|&cell| (cell, 2)));
+        states.insert((-1, 1), 1);
+        assert_eq!(engine.states(), states);
+    }
+
+    #[test]
+    fn states_past_the_rule_are_an_error() {
+        let rule: Rule = "B2/S/C3".parse().unwrap();
+        let seed: HashMap<Cell, u8> = vec![((0, 0), 1), ((0, 1), 255)].into_iter().collect();
+        assert_eq!(
+            Backend::HashSet.create_with_states(seed, rule, Topology::Plane).err(),
+            Some(EngineError::InvalidState(255, 3))
+        );
+        assert_eq!(rule.decay(2), 0);
+        assert_eq!(rule.decay(255), 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "step_pow2 can jump at most")]
+    fn step_pow2_past_the_limit_panics() {
+        let mut engine = HashSetEngine::new(soup(5, 16), Rule::conway(), Topology::Plane);
+        engine.step_pow2(128);
+    }
+}
@@ -0,0 +1,462 @@
+//! HashLife, Bill Gosper's memoized quadtree algorithm.
+//!
+//! The universe is a quadtree where identical subtrees are shared, so a node is just the ids of
+//! its four children. A node of level k covers a 2^k square and its result is the center 2^(k-1)
+//! square moved forward up to 2^(k-2) generations. Results are memoized per node, repeated
+//! structure in space and time is only ever computed once which is what lets regular patterns
+//! run for billions of generations.
+
+use std::collections::{HashMap, HashSet};
+
+use crate::coordinate::Coordinate;
+use crate::engine::{Backend, Engine, EngineError, MAX_STEP_POW2};
+use crate::rule::Rule;
+use crate::{Cell, NEIGHBOR_OFFSETS};
+
+type NodeId = u32;
+
+/// Level 0 nodes are single cells and always have these ids.
+const DEAD: NodeId = 0;
+const ALIVE: NodeId = 1;
+
+/// Once this many nodes exist everything not reachable from the root is dropped before the next
+/// step.
+const GC_THRESHOLD: usize = 1 << 22;
+
+/// Coordinates are i128 internally, the root can't grow past covering that whole range.
+const MAX_LEVEL: u8 = 127;
+
+#[derive(Debug, Clone, Copy)]
+struct Node {
+    level: u8,
+    population: u128,
+    /// nw, ne, sw, se
+    children: [NodeId; 4]
+}
+
+pub struct HashLife {
+    nodes: Vec<Node>,
+    interned: HashMap<[NodeId; 4], NodeId>,
+    /// (node, j) to the center of node moved forward 2^j generations
+    results: HashMap<(NodeId, u8), NodeId>,
+    /// Canonical empty node for each level
+    empty: Vec<NodeId>,
+    /// One generation of a 4x4 block, indexed by its cells as bits in row major order. The four
+    /// bits of the result are the center 2x2 in the same order.
+    base: Vec<u8>,
+    /// Always centered on the origin, a root of level k covers -2^(k-1) up to 2^(k-1).
+    root: NodeId,
+    generation: u128
+}
+
+impl HashLife {
+    /// Fails if a seed cell is outside of the range the root can cover, beyond ±2^126.
+    pub fn new<C: Coordinate>(seed: Vec<Cell<C>>, rule: Rule) -> Result<HashLife, EngineError> {
+        let leaf = Node { level: 0, population: 0, children: [DEAD; 4] };
+        let mut hashlife = HashLife {
+            nodes: vec![leaf, Node { population: 1, ..leaf }],
+            interned: HashMap::new(),
+            results: HashMap::new(),
+            empty: vec![DEAD],
+            base: base_table(&rule),
+            root: DEAD,
+            generation: 0
+        };
+
+        let mut cells: Vec<(i128, i128)> = seed
+            .into_iter()
+            .map(|(row, col)| (row.to_i128(), col.to_i128()))
+            .collect();
+        cells.sort_unstable();
+        cells.dedup();
+
+        // Smallest root centered on the origin that holds every cell
+        let mut level = 3;
+        while cells.iter().any(|&(row, col)| !fits(level, row) || !fits(level, col)) {
+            if level == MAX_LEVEL {
+                return Err(EngineError::OutOfRange(Backend::HashLife));
+            }
+            level += 1;
+        }
+
+        let half = 1i128 << (level - 1);
+        hashlife.root = hashlife.build(level, -half, -half, cells);
+        Ok(hashlife)
+    }
+
+    pub fn generation(&self) -> u128 {
+        self.generation
+    }
+
+    /// Number of live cells without having to collect them.
+    pub fn population(&self) -> u128 {
+        self.nodes[self.root as usize].population
+    }
+
+    fn build(&mut self, level: u8, top: i128, left: i128, cells: Vec<(i128, i128)>) -> NodeId {
+        if cells.is_empty() {
+            return self.empty(level);
+        }
+        if level == 0 {
+            return ALIVE;
+        }
+
+        let half = 1i128 << (level - 1);
+        let mut quadrants: [Vec<(i128, i128)>; 4] = Default::default();
+        for (row, col) in cells {
+            let index = (row >= top + half) as usize * 2 + (col >= left + half) as usize;
+            quadrants[index].push((row, col));
+        }
+
+        let [nw, ne, sw, se] = quadrants;
+        let nw = self.build(level - 1, top, left, nw);
+        let ne = self.build(level - 1, top, left + half, ne);
+        let sw = self.build(level - 1, top + half, left, sw);
+        let se = self.build(level - 1, top + half, left + half, se);
+        self.join(nw, ne, sw, se)
+    }
+
+    fn node(&self, id: NodeId) -> Node {
+        self.nodes[id as usize]
+    }
+
+    /// Canonical node with the given children, created the first time it is asked for.
+    fn join(&mut self, nw: NodeId, ne: NodeId, sw: NodeId, se: NodeId) -> NodeId {
+        let children = [nw, ne, sw, se];
+        if let Some(&id) = self.interned.get(&children) {
+            return id;
+        }
+
+        let population = children.iter().map(|&child| self.node(child).population).sum();
+        let id = self.nodes.len() as NodeId;
+        self.nodes.push(Node {
+            level: self.node(nw).level + 1,
+            population,
+            children
+        });
+        self.interned.insert(children, id);
+        id
+    }
+
+    fn empty(&mut self, level: u8) -> NodeId {
+        while self.empty.len() <= level as usize {
+            let below = self.empty[self.empty.len() - 1];
+            let id = self.join(below, below, below, below);
+            self.empty.push(id);
+        }
+
+        self.empty[level as usize]
+    }
+
+    /// Center half of a node, one level down.
+    fn center(&mut self, id: NodeId) -> NodeId {
+        let [nw, ne, sw, se] = self.node(id).children;
+        let nw = self.node(nw).children[3];
+        let ne = self.node(ne).children[2];
+        let sw = self.node(sw).children[1];
+        let se = self.node(se).children[0];
+        self.join(nw, ne, sw, se)
+    }
+
+    /// Center of `id` moved forward 2^j generations, j can be at most the node's level - 2.
+    fn advance(&mut self, id: NodeId, j: u8) -> NodeId {
+        let node = self.node(id);
+        if node.population == 0 {
+            return self.empty(node.level - 1);
+        }
+        if node.level == 2 {
+            return self.base_step(id);
+        }
+        if let Some(&result) = self.results.get(&(id, j)) {
+            return result;
+        }
+
+        // The 9 overlapping sub squares one level down, row by row
+        let [nw, ne, sw, se] = node.children;
+        let [_, nw_ne, nw_sw, nw_se] = self.node(nw).children;
+        let [ne_nw, _, ne_sw, ne_se] = self.node(ne).children;
+        let [sw_nw, sw_ne, _, sw_se] = self.node(sw).children;
+        let [se_nw, se_ne, se_sw, _] = self.node(se).children;
+
+        let squares = [
+            nw,
+            self.join(nw_ne, ne_nw, nw_se, ne_sw),
+            ne,
+            self.join(nw_sw, nw_se, sw_nw, sw_ne),
+            self.join(nw_se, ne_sw, sw_ne, se_nw),
+            self.join(ne_sw, ne_se, se_nw, se_ne),
+            sw,
+            self.join(sw_ne, se_nw, sw_se, se_sw),
+            se
+        ];
+
+        // A full speed step spends half the time on each of the two stages, a slower one just
+        // recenters in the first stage and leaves all the time to the second.
+        let full_speed = j == node.level - 2;
+        let mut stage = [DEAD; 9];
+        for (index, &square) in squares.iter().enumerate() {
+            stage[index] = if full_speed {
+                self.advance(square, j - 1)
+            } else {
+                self.center(square)
+            };
+        }
+
+        let second_j = if full_speed { j - 1 } else { j };
+        let quads = [
+            self.join(stage[0], stage[1], stage[3], stage[4]),
+            self.join(stage[1], stage[2], stage[4], stage[5]),
+            self.join(stage[3], stage[4], stage[6], stage[7]),
+            self.join(stage[4], stage[5], stage[7], stage[8])
+        ];
+        let nw = self.advance(quads[0], second_j);
+        let ne = self.advance(quads[1], second_j);
+        let sw = self.advance(quads[2], second_j);
+        let se = self.advance(quads[3], second_j);
+
+        let result = self.join(nw, ne, sw, se);
+        self.results.insert((id, j), result);
+        result
+    }
+
+    /// A 4x4 node moved forward one generation through the lookup table.
+    fn base_step(&mut self, id: NodeId) -> NodeId {
+        let mut index = 0;
+        for row in 0..4 {
+            for col in 0..4 {
+                let quadrant = self.node(id).children[(row / 2) * 2 + col / 2];
+                let cell = self.node(quadrant).children[(row % 2) * 2 + col % 2];
+                if cell == ALIVE {
+                    index |= 1 << (row * 4 + col);
+                }
+            }
+        }
+
+        let bits = self.base[index];
+        let leaf = |bit: u8| if bits & (1 << bit) != 0 { ALIVE } else { DEAD };
+        self.join(leaf(0), leaf(1), leaf(2), leaf(3))
+    }
+
+    /// Wraps the root in a border of empty space, doubling its size while keeping it centered.
+    fn expand(&mut self) {
+        let root = self.node(self.root);
+
+        let [nw, ne, sw, se] = root.children;
+        let e = self.empty(root.level - 1);
+        let nw = self.join(e, e, e, nw);
+        let ne = self.join(e, e, ne, e);
+        let sw = self.join(e, sw, e, e);
+        let se = self.join(se, e, e, e);
+        self.root = self.join(nw, ne, sw, se);
+    }
+
+    /// True if every live cell of the root sits in its center quarter.
+    fn is_padded(&self) -> bool {
+        let root = self.node(self.root);
+        let [nw, ne, sw, se] = root.children;
+        let inner = [
+            self.node(self.node(nw).children[3]).children[3],
+            self.node(self.node(ne).children[2]).children[2],
+            self.node(self.node(sw).children[1]).children[1],
+            self.node(self.node(se).children[0]).children[0]
+        ];
+
+        inner.iter().map(|&id| self.node(id).population).sum::<u128>() == root.population
+    }
+
+    /// Rebuilds the node store with only what the root still uses.
+    fn collect_garbage(&mut self) {
+        let old_nodes = std::mem::take(&mut self.nodes);
+        self.nodes.extend_from_slice(&old_nodes[..2]);
+        self.interned.clear();
+        self.results.clear();
+        self.empty = vec![DEAD];
+
+        let mut copied = HashMap::new();
+        self.root = self.copy_node(&old_nodes, self.root, &mut copied);
+    }
+
+    fn copy_node(&mut self, old_nodes: &[Node], id: NodeId, copied: &mut HashMap<NodeId, NodeId>) -> NodeId {
+        if id == DEAD || id == ALIVE {
+            return id;
+        }
+        if let Some(&new_id) = copied.get(&id) {
+            return new_id;
+        }
+
+        let [nw, ne, sw, se] = old_nodes[id as usize].children;
+        let nw = self.copy_node(old_nodes, nw, copied);
+        let ne = self.copy_node(old_nodes, ne, copied);
+        let sw = self.copy_node(old_nodes, sw, copied);
+        let se = self.copy_node(old_nodes, se, copied);
+        let new_id = self.join(nw, ne, sw, se);
+        copied.insert(id, new_id);
+        new_id
+    }
+
+    fn collect_cells<C: Coordinate>(&self, id: NodeId, top: i128, left: i128, cells: &mut HashSet<Cell<C>>) {
+        let node = self.node(id);
+        if node.population == 0 {
+            return;
+        }
+
+        if node.level == 0 {
+            // Cells that don't fit in the caller's coordinate type are left out
+            if let (Some(row), Some(col)) = (C::from_i128(top), C::from_i128(left)) {
+                cells.insert((row, col));
+            }
+            return;
+        }
+
+        let half = 1i128 << (node.level - 1);
+        let [nw, ne, sw, se] = node.children;
+        self.collect_cells(nw, top, left, cells);
+        self.collect_cells(ne, top, left + half, cells);
+        self.collect_cells(sw, top + half, left, cells);
+        self.collect_cells(se, top + half, left + half, cells);
+    }
+}
+
+impl<C: Coordinate> Engine<C> for HashLife {
+    fn cells(&self) -> HashSet<Cell<C>> {
+        let mut cells = HashSet::new();
+        let level = self.node(self.root).level;
+        let half = 1i128 << (level - 1);
+        self.collect_cells(self.root, -half, -half, &mut cells);
+        cells
+    }
+
+    fn population(&self) -> u128 {
+        HashLife::population(self)
+    }
+
+    fn step(&mut self) {
+        Engine::<C>::step_pow2(self, 0);
+    }
+
+    fn step_pow2(&mut self, k: u32) {
+        assert!(k <= MAX_STEP_POW2, "step_pow2 can jump at most 2^{} generations, got 2^{}", MAX_STEP_POW2, k);
+        if self.nodes.len() > GC_THRESHOLD {
+            self.collect_garbage();
+        }
+
+        // Cells spread at most one cell per generation so with the pattern in the center quarter
+        // and at least 2^k cells of margin nothing can fall off the result. A root covering the
+        // whole range can't grow, cells moving past its edge are dropped the way the plane drops
+        // neighbors past the range of the Coordinate type.
+        let k = k as u8;
+        while self.node(self.root).level < MAX_LEVEL && (self.node(self.root).level < k + 3 || !self.is_padded()) {
+            self.expand();
+        }
+
+        self.root = self.advance(self.root, k);
+        self.generation += 1 << k;
+    }
+}
+
+/// True if `value` is inside a root of the given level centered on the origin.
+fn fits(level: u8, value: i128) -> bool {
+    let half = 1i128 << (level - 1);
+    value >= -half && value < half
+}
+
+fn base_table(rule: &Rule) -> Vec<u8> {
+    let mut table = vec![0; 1 << 16];
+    let alive = |index: usize, row: usize, col: usize| index & (1 << (row * 4 + col)) != 0;
+
+    for (index, entry) in table.iter_mut().enumerate() {
+        for (bit, &(row, col)) in [(1, 1), (1, 2), (2, 1), (2, 2)].iter().enumerate() {
+            let mut neighbors = 0;
+            for (neighbor, &(row_offset, col_offset)) in NEIGHBOR_OFFSETS.iter().enumerate() {
+                if alive(index, (row as i8 + row_offset) as usize, (col as i8 + col_offset) as usize) {
+                    neighbors |= 1 << neighbor;
+                }
+            }
+
+            let next = if alive(index, row, col) {
+                rule.survives_with(neighbors)
+            } else {
+                rule.is_born_with(neighbors)
+            };
+            if next {
+                *entry |= 1 << bit;
+            }
+        }
+    }
+
+    table
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{assert_matches_hashset, soup, R_PENTOMINO};
+    use crate::topology::Topology;
+    use crate::GOLGenerationIterator;
+
+    #[test]
+    fn r_pentomino_matches_hashset() {
+        assert_matches_hashset(Box::new(HashLife::new(R_PENTOMINO.to_vec(), Rule::conway()).unwrap()), &R_PENTOMINO, Rule::conway(), 1024);
+    }
+
+    #[test]
+    fn soup_matches_hashset() {
+        let seed = soup(3, 40);
+        assert_matches_hashset(Box::new(HashLife::new(seed.clone(), Rule::conway()).unwrap()), &seed, Rule::conway(), 300);
+
+        let rule: Rule = "B2-a/S12".parse().unwrap();
+        assert_matches_hashset(Box::new(HashLife::new(seed.clone(), rule).unwrap()), &seed, rule, 100);
+    }
+
+    #[test]
+    fn step_pow2_skips_ahead() {
+        let hashset: Vec<_> = GOLGenerationIterator::new(R_PENTOMINO.to_vec(), Rule::conway(), Topology::Plane)
+            .step_by(8)
+            .take(128)
+            .collect();
+        let hashlife: Vec<_> = GOLGenerationIterator::with_backend(R_PENTOMINO.to_vec(), Rule::conway(), Topology::Plane, Backend::HashLife)
+            .unwrap()
+            .with_step_pow2(3)
+            .take(128)
+            .collect();
+        assert_eq!(hashlife, hashset);
+    }
+
+    #[test]
+    fn seed_out_of_range_is_an_error() {
+        let result = HashLife::new(vec![(i128::MAX, 0)], Rule::conway());
+        assert_eq!(result.err(), Some(EngineError::OutOfRange(Backend::HashLife)));
+    }
+
+    #[test]
+    fn growth_past_range_is_dropped() {
+        // A glider heading down and right from just inside the range
+        let edge = (1i128 << 126) - 4;
+        let glider: Vec<Cell<i128>> = vec![(edge, edge + 1), (edge + 1, edge + 2), (edge + 2, edge), (edge + 2, edge + 1), (edge + 2, edge + 2)];
+        let mut hashlife = HashLife::new(glider, Rule::conway()).unwrap();
+        for _ in 0..40 {
+            Engine::<i128>::step(&mut hashlife);
+        }
+        assert!(HashLife::population(&hashlife) < 5);
+    }
+
+    #[test]
+    fn jumps_as_far_as_max_step_pow2() {
+        let glider: Vec<Cell<i128>> = vec![(0, 1), (1, 2), (2, 0), (2, 1), (2, 2)];
+        let mut hashlife = HashLife::new(glider.clone(), Rule::conway()).unwrap();
+        Engine::<i128>::step_pow2(&mut hashlife, MAX_STEP_POW2);
+
+        // A glider moves one cell down and right every 4 generations
+        let moved = 1i128 << (MAX_STEP_POW2 - 2);
+        let expected: HashSet<Cell<i128>> = glider.iter().map(|&(row, col)| (row + moved, col + moved)).collect();
+        assert_eq!(Engine::<i128>::cells(&hashlife), expected);
+        assert_eq!(hashlife.generation(), 1 << MAX_STEP_POW2);
+    }
+
+    #[test]
+    #[should_panic(expected = "step_pow2 can jump at most")]
+    fn step_pow2_past_the_limit_panics() {
+        let mut hashlife = HashLife::new(R_PENTOMINO.to_vec(), Rule::conway()).unwrap();
+        Engine::<i64>::step_pow2(&mut hashlife, MAX_STEP_POW2 + 1);
+    }
+}
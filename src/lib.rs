@@ -0,0 +1,478 @@
+use std::collections::{HashMap, HashSet};
+use std::thread;
+
+pub mod analysis;
+pub mod apgcode;
+pub mod automaton;
+pub mod census;
+pub mod coordinate;
+pub mod engine;
+pub mod hashlife;
+pub mod ltl;
+pub mod objects;
+pub mod pattern;
+pub mod render;
+pub mod rule;
+pub mod soup;
+pub mod table;
+pub mod tiled;
+pub mod topology;
+
+use coordinate::Coordinate;
+use engine::{Backend, Engine, EngineError, HashSetEngine};
+use rule::{AnyRule, Neighborhood, Rule};
+use topology::Topology;
+
+/// Default coordinate type, signed so patterns can grow in every direction from the origin.
+pub type CellCoordinate = i64;
+pub type Cell<C = CellCoordinate> = (C, C);
+
+/// Smallest rectangle holding every cell as its (top left, bottom right) corners, None when there
+/// are no cells.
+pub fn bounding_box<'a, C: Coordinate>(
+    cells: impl IntoIterator<Item = &'a Cell<C>>
+) -> Option<(Cell<C>, Cell<C>)> {
+    cells.into_iter().fold(None, |bounds, &(row, col)| match bounds {
+        None => Some(((row, col), (row, col))),
+        Some(((top, left), (bottom, right))) => Some((
+            (top.min(row), left.min(col)),
+            (bottom.max(row), right.max(col))
+        ))
+    })
+}
+
+/// Offsets of the 8 cells surrounding a cell, row by row from the top left.
+const NEIGHBOR_OFFSETS: [(i8, i8); 8] = [
+    (-1, -1), (-1, 0), (-1, 1),
+    (0, -1),           (0, 1),
+    (1, -1),  (1, 0),  (1, 1)
+];
+
+#[derive(Debug)]
+pub struct NeighborIterator<C: Coordinate> {
+    current_cell: Cell<C>,
+    topology: Topology<C>,
+    neighborhood: Neighborhood,
+    next_offset: usize
+}
+
+impl<C: Coordinate> NeighborIterator<C>  {
+    pub fn new(cell: Cell<C>, topology: Topology<C>) -> NeighborIterator<C> {
+        NeighborIterator {
+            current_cell: cell,
+            topology,
+            neighborhood: Neighborhood::Moore,
+            next_offset: 0
+        }
+    }
+
+    /// Emits only the neighbors in `neighborhood` rather than all 8.
+    pub fn with_neighborhood(mut self, neighborhood: Neighborhood) -> NeighborIterator<C> {
+        self.neighborhood = neighborhood;
+        self
+    }
+}
+
+/// Iterates through all the possible neighbors excluding the current cell.
+///
+/// On the plane this iterator only emits the viable cells. Any cell that is outside the bounds of
+/// the coordinate data type are not generated, so emitted coordinates are between (including) the
+/// min and max of the Coordinate type and only cells at those extremes lose neighbors.
+///
+/// On a torus neighbors past an edge wrap around to the opposite side, so the whole neighborhood is
+/// always emitted. Boards narrower than 3 cells will emit the same neighbor more than once.
+impl<C: Coordinate> Iterator for NeighborIterator<C> {
+    type Item = Cell<C>;
+    fn next(&mut self) -> Option<Cell<C>> {
+        while let Some(offset) = NEIGHBOR_OFFSETS.get(self.next_offset) {
+            let in_neighborhood = self.neighborhood.mask() & (1 << self.next_offset) != 0;
+            self.next_offset += 1;
+            if !in_neighborhood {
+                continue;
+            }
+
+            // Neighbors off the edge of the plane are skipped rather than ending the iteration
+            if let Some(neighbor) = self.topology.neighbor(self.current_cell, *offset) {
+                return Some(neighbor);
+            }
+        }
+
+        None
+    }
+}
+
+pub struct GOLGenerationIterator<C: Coordinate = CellCoordinate> {
+    engine: Box<dyn Engine<C>>,
+    step_log2: u32
+}
+
+impl<C: Coordinate> GOLGenerationIterator<C> {
+    /// Runs on the HashSet backend, seed cells outside of a torus board are wrapped onto it.
+    pub fn new(seed: Vec<Cell<C>>, rule: Rule, topology: Topology<C>) -> GOLGenerationIterator<C> {
+        GOLGenerationIterator::from_engine(Box::new(HashSetEngine::new(seed, rule, topology)))
+    }
+
+    /// Fails if the backend can't run the rule on the topology.
+    pub fn with_backend(
+        seed: Vec<Cell<C>>,
+        rule: impl Into<AnyRule>,
+        topology: Topology<C>,
+        backend: Backend
+    ) -> Result<GOLGenerationIterator<C>, EngineError> {
+        Ok(GOLGenerationIterator::from_engine(backend.create(seed, rule, topology)?))
+    }
+
+    /// Starts from the state of each cell rather than just live cells, such as a Wireworld
+    /// circuit. Fails if the backend can't run the rule on the topology or from those states.
+    pub fn with_states(
+        seed: HashMap<Cell<C>, u8>,
+        rule: impl Into<AnyRule>,
+        topology: Topology<C>,
+        backend: Backend
+    ) -> Result<GOLGenerationIterator<C>, EngineError> {
+        Ok(GOLGenerationIterator::from_engine(backend.create_with_states(seed, rule, topology)?))
+    }
+
+    pub fn from_engine(engine: Box<dyn Engine<C>>) -> GOLGenerationIterator<C> {
+        GOLGenerationIterator {
+            engine,
+            step_log2: 0
+        }
+    }
+
+    /// Makes every call to next move forward 2^k generations rather than one, so HashLife can
+    /// jump far ahead between the generations it yields. Panics if k is over
+    /// `engine::MAX_STEP_POW2`.
+    pub fn with_step_pow2(mut self, k: u32) -> GOLGenerationIterator<C> {
+        assert!(k <= engine::MAX_STEP_POW2, "step_pow2 can jump at most 2^{} generations, got 2^{}", engine::MAX_STEP_POW2, k);
+        self.step_log2 = k;
+        self
+    }
+
+    /// Yields the state of every cell rather than just the live ones, for rules whose cells do
+    /// more than live and die.
+    pub fn into_states(self) -> StatesIterator<C> {
+        StatesIterator {
+            engine: self.engine,
+            step_log2: self.step_log2
+        }
+    }
+}
+
+impl<C: Coordinate> Iterator for GOLGenerationIterator<C> {
+    type Item = HashSet<Cell<C>>;
+    fn next(&mut self) -> Option<HashSet<Cell<C>>> {
+        Some(self.engine.advance(self.step_log2))
+    }
+}
+
+/// Generations as the state of every cell that isn't dead, 1 being alive.
+pub struct StatesIterator<C: Coordinate = CellCoordinate> {
+    engine: Box<dyn Engine<C>>,
+    step_log2: u32
+}
+
+impl<C: Coordinate> Iterator for StatesIterator<C> {
+    type Item = HashMap<Cell<C>, u8>;
+    fn next(&mut self) -> Option<HashMap<Cell<C>, u8>> {
+        let states = self.engine.states();
+        self.engine.step_pow2(self.step_log2);
+        Some(states)
+    }
+}
+
+/// Each neighbor of a live cell along with the bit `Rule::is_born_with` uses for the live cell as
+/// seen from that neighbor, which is the offset pointing the opposite way. Every neighborhood is
+/// symmetric so that offset is in the neighborhood too.
+fn masked_neighbors<C: Coordinate>(
+    cell: Cell<C>,
+    topology: &Topology<C>,
+    neighborhood: Neighborhood
+) -> impl Iterator<Item = (Cell<C>, u8)> + '_ {
+    NEIGHBOR_OFFSETS
+        .iter()
+        .enumerate()
+        .filter(move |&(index, _)| neighborhood.mask() & (1 << index) != 0)
+        .filter_map(move |(index, &offset)| Some((topology.neighbor(cell, offset)?, 1 << (7 - index))))
+}
+
+/// Whether `cell` is alive next generation given which of its neighbors are alive now.
+fn alive_next<C: Coordinate>(current_gen: &HashSet<Cell<C>>, rule: &Rule, cell: &Cell<C>, neighbors: u8) -> bool {
+    if current_gen.contains(cell) {
+        rule.survives_with(neighbors)
+    } else {
+        rule.is_born_with(neighbors)
+    }
+}
+
+/// Adds the live cells with no live neighbors to `next` when the rule has S0, they never made it
+/// into the neighbor masks.
+fn keep_isolated<C: Coordinate>(
+    current_gen: &HashSet<Cell<C>>,
+    rule: &Rule,
+    topology: &Topology<C>,
+    next: &mut HashSet<Cell<C>>
+) {
+    if !rule.survives_with(0) {
+        return;
+    }
+
+    for cell in current_gen.iter() {
+        let isolated = NeighborIterator::new(*cell, *topology)
+            .with_neighborhood(rule.neighborhood())
+            .all(|neighbor| !current_gen.contains(&neighbor));
+        if isolated {
+            next.insert(*cell);
+        }
+    }
+}
+
+/// Computes the generation after `current_gen` in a single pass.
+///
+/// Every live cell sets its own bit in the neighbor mask of each of its neighbors, so after one
+/// sweep the map holds which neighbors are alive for every cell that could be alive next
+/// generation. That is all a non-totalistic rule needs and the count is just the bits set. Those
+/// are the only cells the rule needs to look at, a cell with no live neighbors can only survive
+/// on S0.
+pub fn compute_next_gen<C: Coordinate>(
+    current_gen: &HashSet<Cell<C>>,
+    rule: &Rule,
+    topology: &Topology<C>
+) -> HashSet<Cell<C>> {
+    let mut neighbor_masks: HashMap<Cell<C>, u8> = HashMap::with_capacity(current_gen.len() * 4);
+
+    for cell in current_gen.iter() {
+        for (neighbor, bit) in masked_neighbors(*cell, topology, rule.neighborhood()) {
+            *neighbor_masks.entry(neighbor).or_insert(0) |= bit;
+        }
+    }
+
+    let mut next: HashSet<Cell<C>> = neighbor_masks
+        .into_iter()
+        .filter(|(cell, neighbors)| alive_next(current_gen, rule, cell, *neighbors))
+        .map(|(cell, _)| cell)
+        .collect();
+
+    keep_isolated(current_gen, rule, topology, &mut next);
+
+    next
+}
+
+/// Same result as `compute_next_gen` with the work spread over `threads` threads.
+///
+/// Cells are sharded by row. Each thread marks the neighbors of its own slice of the live cells
+/// into one mask map per shard, then each thread merges every mask map for one shard and applies
+/// the rule to it. The shards don't overlap so the union of what the threads keep is exactly the
+/// serial result.
+pub fn compute_next_gen_parallel<C: Coordinate>(
+    current_gen: &HashSet<Cell<C>>,
+    rule: &Rule,
+    topology: &Topology<C>,
+    threads: usize
+) -> HashSet<Cell<C>> {
+    if threads <= 1 {
+        return compute_next_gen(current_gen, rule, topology);
+    }
+
+    let shard = |(row, _): &Cell<C>| row.to_i128().rem_euclid(threads as i128) as usize;
+    let live: Vec<Cell<C>> = current_gen.iter().copied().collect();
+    let chunk_size = live.len().div_ceil(threads).max(1);
+
+    let partial_counts: Vec<Vec<HashMap<Cell<C>, u8>>> = thread::scope(|scope| {
+        let workers: Vec<_> = live
+            .chunks(chunk_size)
+            .map(|chunk| {
+                scope.spawn(move || {
+                    let mut counts: Vec<HashMap<Cell<C>, u8>> = vec![HashMap::new(); threads];
+                    for cell in chunk {
+                        for (neighbor, bit) in masked_neighbors(*cell, topology, rule.neighborhood()) {
+                            *counts[shard(&neighbor)].entry(neighbor).or_insert(0) |= bit;
+                        }
+                    }
+                    counts
+                })
+            })
+            .collect();
+
+        workers.into_iter().map(|worker| worker.join().expect("counting thread panicked")).collect()
+    });
+
+    // Regroup so each shard's count maps from every worker are together
+    let mut shards: Vec<Vec<HashMap<Cell<C>, u8>>> = (0..threads).map(|_| vec![]).collect();
+    for counts in partial_counts {
+        for (index, count) in counts.into_iter().enumerate() {
+            shards[index].push(count);
+        }
+    }
+
+    let kept: Vec<Vec<Cell<C>>> = thread::scope(|scope| {
+        let workers: Vec<_> = shards
+            .into_iter()
+            .map(|maps| {
+                scope.spawn(move || {
+                    let mut maps = maps.into_iter();
+                    let mut neighbor_masks = maps.next().unwrap_or_default();
+                    for map in maps {
+                        for (cell, neighbors) in map {
+                            *neighbor_masks.entry(cell).or_insert(0) |= neighbors;
+                        }
+                    }
+
+                    neighbor_masks
+                        .into_iter()
+                        .filter(|(cell, neighbors)| alive_next(current_gen, rule, cell, *neighbors))
+                        .map(|(cell, _)| cell)
+                        .collect::<Vec<Cell<C>>>()
+                })
+            })
+            .collect();
+
+        workers.into_iter().map(|worker| worker.join().expect("rule thread panicked")).collect()
+    });
+
+    let mut next: HashSet<Cell<C>> = HashSet::with_capacity(kept.iter().map(Vec::len).sum());
+    for cells in kept {
+        next.extend(cells);
+    }
+
+    keep_isolated(current_gen, rule, topology, &mut next);
+
+    next
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    pub fn cells<C: Coordinate>(cells: &[(i128, i128)]) -> HashSet<Cell<C>> {
+        cells.iter().map(|&(row, col)| (C::from_i128(row).unwrap(), C::from_i128(col).unwrap())).collect()
+    }
+
+    pub const GLIDER: [(i128, i128); 5] = [(0, 1), (1, 2), (2, 0), (2, 1), (2, 2)];
+
+    fn glider_moves_diagonally<C: Coordinate>() {
+        let mut generations = GOLGenerationIterator::new(cells::<C>(&GLIDER).into_iter().collect(), Rule::conway(), Topology::Plane);
+        let moved: Vec<_> = GLIDER.iter().map(|&(row, col)| (row + 1, col + 1)).collect();
+        assert_eq!(generations.nth(4), Some(cells(&moved)));
+    }
+
+    fn blinker_has_period_2<C: Coordinate>() {
+        let horizontal = cells::<C>(&[(1, 0), (1, 1), (1, 2)]);
+        let vertical = cells::<C>(&[(0, 1), (1, 1), (2, 1)]);
+        let generations: Vec<_> = GOLGenerationIterator::new(horizontal.iter().copied().collect(), Rule::conway(), Topology::Plane)
+            .take(3)
+            .collect();
+        assert_eq!(generations, vec![horizontal.clone(), vertical, horizontal]);
+    }
+
+    fn blinker_wraps_around_torus<C: Coordinate>() {
+        let topology = Topology::torus(C::from_i128(8).unwrap(), C::from_i128(8).unwrap()).unwrap();
+        let horizontal = cells::<C>(&[(0, 7), (0, 0), (0, 1)]);
+        let vertical = cells::<C>(&[(7, 0), (0, 0), (1, 0)]);
+        let generations: Vec<_> = GOLGenerationIterator::new(horizontal.iter().copied().collect(), Rule::conway(), topology)
+            .take(3)
+            .collect();
+        assert_eq!(generations, vec![horizontal.clone(), vertical, horizontal]);
+    }
+
+    fn plane_edges_lose_neighbors<C: Coordinate>() {
+        let zero = C::ZERO;
+        assert_eq!(NeighborIterator::new((C::MIN, C::MIN), Topology::Plane).count(), 3);
+        assert_eq!(NeighborIterator::new((C::MAX, C::MAX), Topology::Plane).count(), 3);
+        assert_eq!(NeighborIterator::new((C::MIN, zero), Topology::Plane).count(), 5);
+        assert_eq!(NeighborIterator::new((zero, C::MAX), Topology::Plane).count(), 5);
+        assert_eq!(NeighborIterator::new((zero, zero), Topology::Plane).count(), 8);
+
+        // A blinker against the top edge can't grow past it
+        let top = C::MIN.to_i128();
+        let seed = cells::<C>(&[(top, 0), (top, 1), (top, 2)]);
+        let next = GOLGenerationIterator::new(seed.into_iter().collect(), Rule::conway(), Topology::Plane).nth(1);
+        assert_eq!(next, Some(cells(&[(top, 1), (top + 1, 1)])));
+    }
+
+    /// Cells of a `size` square filled at random about half full, the same for the same seed.
+    pub fn soup(mut seed: u64, size: i64) -> Vec<Cell> {
+        let mut cells = vec![];
+        for row in 0..size {
+            for col in 0..size {
+                // xorshift64
+                seed ^= seed << 13;
+                seed ^= seed >> 7;
+                seed ^= seed << 17;
+                if seed & 1 == 1 {
+                    cells.push((row - size / 2, col - size / 2));
+                }
+            }
+        }
+        cells
+    }
+
+    pub const R_PENTOMINO: [Cell; 5] = [(0, 1), (0, 2), (1, 0), (1, 1), (2, 1)];
+
+    /// Checks `engine` yields the same generations as the HashSet engine from the same seed.
+    pub fn assert_matches_hashset(mut engine: Box<dyn Engine<CellCoordinate>>, seed: &[Cell], rule: Rule, generations: usize) {
+        let mut expected = HashSetEngine::new(seed.to_vec(), rule, Topology::Plane);
+        for generation in 0..generations {
+            assert_eq!(engine.population(), expected.population(), "population at generation {}", generation);
+            assert_eq!(engine.cells(), expected.cells(), "generation {}", generation);
+            engine.step();
+            expected.step();
+        }
+    }
+
+    #[test]
+    fn parallel_matches_serial() {
+        let torus = Topology::torus(40, 30).unwrap();
+        for rule in ["B3/S23", "B2-a/S12", "B3/S023"] {
+            let rule: Rule = rule.parse().unwrap();
+            for topology in [Topology::Plane, torus] {
+                let mut current: HashSet<Cell> = soup(7, 32).into_iter().map(|cell| topology.wrap(cell)).collect();
+                for generation in 0..20 {
+                    let serial = compute_next_gen(&current, &rule, &topology);
+                    for threads in [1, 2, 3, 7] {
+                        assert_eq!(
+                            compute_next_gen_parallel(&current, &rule, &topology, threads),
+                            serial,
+                            "{} on {:?} with {} threads at generation {}",
+                            rule,
+                            topology,
+                            threads,
+                            generation
+                        );
+                    }
+                    current = serial;
+                }
+            }
+        }
+    }
+
+    macro_rules! coordinate_tests {
+        ($($module:ident: $coordinate:ty),*) => {
+            $(
+                mod $module {
+                    #[test]
+                    fn glider_moves_diagonally() {
+                        super::glider_moves_diagonally::<$coordinate>();
+                    }
+
+                    #[test]
+                    fn blinker_has_period_2() {
+                        super::blinker_has_period_2::<$coordinate>();
+                    }
+
+                    #[test]
+                    fn blinker_wraps_around_torus() {
+                        super::blinker_wraps_around_torus::<$coordinate>();
+                    }
+
+                    #[test]
+                    fn plane_edges_lose_neighbors() {
+                        super::plane_edges_lose_neighbors::<$coordinate>();
+                    }
+                }
+            )*
+        };
+    }
+
+    coordinate_tests!(width_32: i32, width_64: i64, width_128: i128);
+}
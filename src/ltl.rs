@@ -0,0 +1,486 @@
+//! Larger than Life, Life-like rules over a neighborhood of range R rather than the 8 cells
+//! touching a cell, written `R5,C0,M1,S34..58,B34..45,NM` as in Golly.
+//!
+//! A neighborhood holds up to (2R+1)^2 cells so counting each one separately gets slow quickly.
+//! Instead every step builds a summed-area table over the live cells, after which the live cells
+//! in any rectangle are 4 lookups. A Moore neighborhood is one rectangle, von Neumann and circular
+//! neighborhoods are added up a row at a time as 2R+1 rectangles one row tall.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::str::FromStr;
+
+use crate::coordinate::Coordinate;
+use crate::engine::{advance_generation, Engine, Generation};
+use crate::rule::RuleParseError;
+use crate::{bounding_box, Cell};
+
+/// Largest range Golly accepts.
+pub const MAX_RANGE: u16 = 500;
+
+/// Which cells within range R count as neighbors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Shape {
+    /// The whole square, `NM`.
+    Moore,
+    /// Cells at most R steps away moving along rows and columns, a diamond, `NN`.
+    VonNeumann,
+    /// Cells whose centers are within R + 1/2 of the middle, `NC`.
+    Circular
+}
+
+/// A Larger than Life rule.
+///
+/// Birth and survival are ranges of counts. With `C` of 3 or more the rule has dying states the
+/// same way Generations rules do, `C0` and `C1` both mean plain live and dead cells.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct LtlRule {
+    range: u16,
+    states: u8,
+    /// Whether a cell counts itself, `M1`
+    middle: bool,
+    survival: (u32, u32),
+    birth: (u32, u32),
+    shape: Shape
+}
+
+impl LtlRule {
+    pub fn range(&self) -> u16 {
+        self.range
+    }
+
+    pub fn shape(&self) -> Shape {
+        self.shape
+    }
+
+    /// Number of states including dead and alive.
+    pub fn states(&self) -> u8 {
+        self.states
+    }
+
+    /// True if a dead cell with `count` live cells in its neighborhood comes to life.
+    pub fn is_born(&self, count: u32) -> bool {
+        (self.birth.0..=self.birth.1).contains(&count)
+    }
+
+    /// True if a live cell with `count` live cells in its neighborhood, itself included for `M1`,
+    /// stays alive.
+    pub fn survives(&self, count: u32) -> bool {
+        (self.survival.0..=self.survival.1).contains(&count)
+    }
+
+    /// How far the neighborhood reaches along each row, from R rows up to R rows down.
+    fn half_widths(&self) -> Vec<u32> {
+        let range = self.range as i64;
+        (-range..=range)
+            .map(|row| match self.shape {
+                Shape::Moore => range,
+                Shape::VonNeumann => range - row.abs(),
+                // Widest column with col^2 + row^2 <= R^2 + R, i.e. within R + 1/2
+                Shape::Circular => {
+                    let limit = range * range + range - row * row;
+                    (0..=range).rev().find(|col| col * col <= limit).unwrap_or(0)
+                }
+            })
+            .map(|width| width as u32)
+            .collect()
+    }
+
+    /// Number of cells counted, the cell itself included only for `M1`.
+    fn size(&self) -> u32 {
+        let cells: u32 = self.half_widths().iter().map(|width| 2 * width + 1).sum();
+        if self.middle {
+            cells
+        } else {
+            cells - 1
+        }
+    }
+}
+
+/// Parses `Rr,Cc,Mm,Smin..max,Bmin..max,Nn` with the parameters in any order and any case. `C`,
+/// `M` and `N` can be left out and default to `C0`, `M0` and `NM`.
+impl FromStr for LtlRule {
+    type Err = RuleParseError;
+
+    fn from_str(s: &str) -> Result<LtlRule, RuleParseError> {
+        let s = s.trim();
+        if s.is_empty() {
+            return Err(RuleParseError::Empty);
+        }
+
+        let (mut range, mut states, mut middle) = (None, None, None);
+        let (mut survival, mut birth, mut shape) = (None, None, None);
+        for parameter in s.split(',') {
+            let parameter = parameter.trim();
+            let invalid = || RuleParseError::InvalidParameter(parameter.to_string());
+            let mut chars = parameter.chars();
+            let name = chars.next().map(|name| name.to_ascii_uppercase()).ok_or_else(invalid)?;
+            let value = chars.as_str();
+
+            match name {
+                'R' => {
+                    let value = value.parse().ok().filter(|range| (1..=MAX_RANGE).contains(range));
+                    set(&mut range, value.ok_or_else(invalid)?, name)?
+                }
+                // C0 and C1 are both the usual two states
+                'C' => set(&mut states, value.parse::<u8>().map_err(|_| invalid())?.max(2), name)?,
+                'M' => match value {
+                    "0" => set(&mut middle, false, name)?,
+                    "1" => set(&mut middle, true, name)?,
+                    _ => return Err(invalid())
+                },
+                'S' => set(&mut survival, parse_span(value).ok_or_else(invalid)?, name)?,
+                'B' => set(&mut birth, parse_span(value).ok_or_else(invalid)?, name)?,
+                'N' => match value.to_ascii_uppercase().as_str() {
+                    "M" => set(&mut shape, Shape::Moore, name)?,
+                    "N" => set(&mut shape, Shape::VonNeumann, name)?,
+                    "C" => set(&mut shape, Shape::Circular, name)?,
+                    _ => return Err(invalid())
+                },
+                _ => return Err(invalid())
+            }
+        }
+
+        let rule = LtlRule {
+            range: range.ok_or(RuleParseError::MissingParameter('R'))?,
+            states: states.unwrap_or(2),
+            middle: middle.unwrap_or(false),
+            survival: survival.ok_or(RuleParseError::MissingParameter('S'))?,
+            birth: birth.ok_or(RuleParseError::MissingParameter('B'))?,
+            shape: shape.unwrap_or(Shape::Moore)
+        };
+
+        if rule.is_born(0) {
+            return Err(RuleParseError::BirthOnZero);
+        }
+        // Counts past the size of the neighborhood can never happen
+        for &(name, (min, max)) in [('S', rule.survival), ('B', rule.birth)].iter() {
+            if max > rule.size() {
+                return Err(RuleParseError::InvalidParameter(format!("{}{}..{}", name, min, max)));
+            }
+        }
+
+        Ok(rule)
+    }
+}
+
+fn set<T>(parameter: &mut Option<T>, value: T, name: char) -> Result<(), RuleParseError> {
+    if parameter.replace(value).is_some() {
+        return Err(RuleParseError::DuplicateSection(name));
+    }
+
+    Ok(())
+}
+
+/// `min..max` with min no more than max.
+fn parse_span(value: &str) -> Option<(u32, u32)> {
+    let (min, max) = value.split_once("..")?;
+    let (min, max) = (min.parse().ok()?, max.parse().ok()?);
+    if min <= max {
+        Some((min, max))
+    } else {
+        None
+    }
+}
+
+/// Always written with every parameter, in Golly's order.
+impl fmt::Display for LtlRule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let shape = match self.shape {
+            Shape::Moore => 'M',
+            Shape::VonNeumann => 'N',
+            Shape::Circular => 'C'
+        };
+
+        write!(
+            f,
+            "R{},C{},M{},S{}..{},B{}..{},N{}",
+            self.range,
+            if self.states > 2 { self.states } else { 0 },
+            self.middle as u8,
+            self.survival.0,
+            self.survival.1,
+            self.birth.0,
+            self.birth.1,
+            shape
+        )
+    }
+}
+
+/// Runs Larger than Life rules on the plane.
+///
+/// Each step sweeps every cell within range of a cluster of live cells, one cluster at a time, so
+/// objects far apart don't cost the space between them.
+pub struct LtlEngine<C: Coordinate> {
+    current_gen: Generation<C>,
+    rule: LtlRule
+}
+
+impl<C: Coordinate> LtlEngine<C> {
+    pub fn new(seed: Vec<Cell<C>>, rule: LtlRule) -> LtlEngine<C> {
+        LtlEngine {
+            current_gen: Generation::new(seed.into_iter().collect(), rule.states()),
+            rule
+        }
+    }
+
+    fn next_gen(&self) -> HashSet<Cell<C>> {
+        let mut next_gen = HashSet::new();
+        for cluster in self.clusters() {
+            self.sweep(&cluster, &mut next_gen);
+        }
+
+        next_gen
+    }
+
+    /// Splits the live cells into groups where no cell has live cells from two groups within
+    /// range. Cells are put into squares 2R + 1 on a side and squares touching each other,
+    /// diagonals included, make a group, so two live cells up to 2R apart always end up together.
+    fn clusters(&self) -> Vec<Vec<(i128, i128)>> {
+        let side = 2 * self.rule.range as i128 + 1;
+        let mut squares: HashMap<(i128, i128), Vec<(i128, i128)>> = HashMap::new();
+        for &(row, col) in self.current_gen.live() {
+            let (row, col) = (row.to_i128(), col.to_i128());
+            squares.entry((row.div_euclid(side), col.div_euclid(side))).or_default().push((row, col));
+        }
+
+        let mut starts: Vec<(i128, i128)> = squares.keys().copied().collect();
+        starts.sort_unstable();
+
+        let mut clusters = vec![];
+        for start in starts {
+            let mut cells = match squares.remove(&start) {
+                Some(cells) => cells,
+                None => continue
+            };
+
+            let mut pending = vec![start];
+            while let Some((row, col)) = pending.pop() {
+                for square in (row - 1..=row + 1).flat_map(|row| (col - 1..=col + 1).map(move |col| (row, col))) {
+                    if let Some(more) = squares.remove(&square) {
+                        cells.extend(more);
+                        pending.push(square);
+                    }
+                }
+            }
+            clusters.push(cells);
+        }
+
+        clusters
+    }
+
+    /// Adds the cells alive next generation around one cluster to `next_gen`. Only the cluster's
+    /// own cells go in the summed-area table, which covers its bounding box grown by R.
+    fn sweep(&self, cluster: &[(i128, i128)], next_gen: &mut HashSet<Cell<C>>) {
+        let ((top, left), (bottom, right)) = match bounding_box(cluster) {
+            Some(bounds) => bounds,
+            None => return
+        };
+
+        // Only cells within range of a live cell can have anything in their neighborhood
+        let reach = self.rule.range as i128;
+        let (top, left) = (top - reach, left - reach);
+        let height = (bottom + reach - top + 1) as usize;
+        let width = (right + reach - left + 1) as usize;
+
+        let sums = SummedArea::new(
+            height,
+            width,
+            cluster.iter().map(|&(row, col)| ((row - top) as usize, (col - left) as usize))
+        );
+        let half_widths = self.rule.half_widths();
+
+        for row in 0..height {
+            for col in 0..width {
+                let alive = sums.rect(row, row, col, col) == 1;
+                let count = match self.rule.shape {
+                    Shape::Moore => sums.rect(
+                        row.saturating_sub(reach as usize),
+                        row + reach as usize,
+                        col.saturating_sub(reach as usize),
+                        col + reach as usize
+                    ),
+                    _ => half_widths
+                        .iter()
+                        .enumerate()
+                        .filter_map(|(index, &half_width)| {
+                            // Rows above the grid are empty
+                            let row = (row + index).checked_sub(reach as usize)?;
+                            let half_width = half_width as usize;
+                            Some(sums.rect(row, row, col.saturating_sub(half_width), col + half_width))
+                        })
+                        .sum()
+                };
+                let count = if alive && !self.rule.middle { count - 1 } else { count };
+
+                let lives = if alive { self.rule.survives(count) } else { self.rule.is_born(count) };
+                if !lives {
+                    continue;
+                }
+
+                // Cells that don't fit the coordinate type fall off the edge of the plane
+                if let (Some(row), Some(col)) = (C::from_i128(top + row as i128), C::from_i128(left + col as i128)) {
+                    next_gen.insert((row, col));
+                }
+            }
+        }
+    }
+}
+
+impl<C: Coordinate> Engine<C> for LtlEngine<C> {
+    fn cells(&self) -> HashSet<Cell<C>> {
+        self.current_gen.live().clone()
+    }
+
+    fn states(&self) -> HashMap<Cell<C>, u8> {
+        self.current_gen.states()
+    }
+
+    fn population(&self) -> u128 {
+        self.current_gen.live().len() as u128
+    }
+
+    fn step(&mut self) {
+        let next_gen = self.next_gen();
+        self.current_gen.replace(next_gen);
+    }
+
+    fn advance(&mut self, k: u32) -> HashSet<Cell<C>> {
+        advance_generation(self, k, |engine| &mut engine.current_gen, LtlEngine::next_gen)
+    }
+}
+
+/// Live cells in every rectangle of a grid in constant time. Entry (row, col) holds the live cells
+/// above and to the left of it, with an extra row and column of zeros at the top and left.
+struct SummedArea {
+    sums: Vec<u32>,
+    height: usize,
+    width: usize
+}
+
+impl SummedArea {
+    fn new(height: usize, width: usize, live: impl Iterator<Item = (usize, usize)>) -> SummedArea {
+        let stride = width + 1;
+        let mut sums = vec![0u32; (height + 1) * stride];
+        for (row, col) in live {
+            sums[(row + 1) * stride + col + 1] = 1;
+        }
+
+        for row in 1..=height {
+            for col in 1..=width {
+                sums[row * stride + col] += sums[(row - 1) * stride + col] + sums[row * stride + col - 1]
+                    - sums[(row - 1) * stride + col - 1];
+            }
+        }
+
+        SummedArea { sums, height, width }
+    }
+
+    /// Live cells in rows `top..=bottom` and columns `left..=right`, the parts past the bottom or
+    /// right of the grid being empty.
+    fn rect(&self, top: usize, bottom: usize, left: usize, right: usize) -> u32 {
+        let (bottom, right) = (bottom.min(self.height - 1) + 1, right.min(self.width - 1) + 1);
+        if top >= bottom || left >= right {
+            return 0;
+        }
+
+        let stride = self.width + 1;
+        let at = |row: usize, col: usize| self.sums[row * stride + col];
+        at(bottom, right) + at(top, left) - at(top, right) - at(bottom, left)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::HashSetEngine;
+    use crate::rule::{AnyRule, Rule};
+    use crate::tests::soup;
+    use crate::topology::Topology;
+
+    /// Next generation found by counting every neighborhood cell by cell.
+    fn brute_force(current: &HashSet<Cell>, rule: &LtlRule) -> HashSet<Cell> {
+        let reach = rule.range as i64;
+        let half_widths = rule.half_widths();
+        let in_range = |(row, col): Cell, (other_row, other_col): Cell| {
+            let (row_offset, col_offset) = (other_row - row, other_col - col);
+            row_offset.abs() <= reach && col_offset.abs() <= half_widths[(row_offset + reach) as usize] as i64
+        };
+
+        let candidates: HashSet<Cell> = current
+            .iter()
+            .flat_map(|&(row, col)| (-reach..=reach).flat_map(move |dr| (-reach..=reach).map(move |dc| (row + dr, col + dc))))
+            .collect();
+        candidates
+            .into_iter()
+            .filter(|&cell| {
+                let alive = current.contains(&cell);
+                let count = current.iter().filter(|&&other| in_range(cell, other)).count() as u32;
+                let count = if alive && !rule.middle { count - 1 } else { count };
+                if alive { rule.survives(count) } else { rule.is_born(count) }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn parsing() {
+        let rule: LtlRule = "r5, m1, s34..58, b34..45".parse().unwrap();
+        assert_eq!(rule.to_string(), "R5,C0,M1,S34..58,B34..45,NM");
+        assert_eq!(rule.to_string().parse(), Ok(rule));
+        assert_eq!("R5,C0,M1,S34..58,B34..45,NM".parse(), Ok(AnyRule::LargerThanLife(rule)));
+
+        let rules = [
+            ("R5,C0,M1,S34..58,NM", RuleParseError::MissingParameter('B')),
+            ("R5,C0,M1,S34..58,B34..45,NX", RuleParseError::InvalidParameter("NX".to_string())),
+            ("R5,R6,S34..58,B34..45", RuleParseError::DuplicateSection('R')),
+            ("R1,S2..9,B3..3", RuleParseError::InvalidParameter("S2..9".to_string())),
+            ("R1,S2..3,B0..3", RuleParseError::BirthOnZero)
+        ];
+        for (rulestring, err) in rules {
+            assert_eq!(rulestring.parse::<LtlRule>(), Err(err), "{}", rulestring);
+        }
+    }
+
+    #[test]
+    fn range_1_is_life() {
+        let rule: LtlRule = "R1,C0,M0,S2..3,B3..3,NM".parse().unwrap();
+        // Two soups far enough apart to be separate clusters
+        let mut seed = soup(11, 24);
+        seed.extend(soup(12, 24).into_iter().map(|(row, col)| (row + 1_000_000_000, col - 1_000_000_000)));
+
+        let mut ltl = LtlEngine::new(seed.clone(), rule);
+        let mut life = HashSetEngine::new(seed, Rule::conway(), Topology::Plane);
+        for generation in 0..100 {
+            assert_eq!(ltl.cells(), life.cells(), "generation {}", generation);
+            ltl.step();
+            life.step();
+        }
+    }
+
+    #[test]
+    fn shapes_match_brute_force() {
+        for rule in ["R3,C0,M1,S8..14,B8..10,NC", "R2,C0,M0,S3..6,B4..5,NN", "R2,C0,M1,S5..10,B5..7,NM"] {
+            let rule: LtlRule = rule.parse().unwrap();
+            let mut engine = LtlEngine::new(soup(4, 20), rule);
+            let mut expected: HashSet<Cell> = soup(4, 20).into_iter().collect();
+            for generation in 0..10 {
+                assert_eq!(engine.cells(), expected, "{} at generation {}", rule, generation);
+                engine.step();
+                expected = brute_force(&expected, &rule);
+            }
+        }
+    }
+
+    #[test]
+    fn far_apart_cells_stay_cheap() {
+        let rule: LtlRule = "R5,C0,M1,S34..58,B34..45,NM".parse().unwrap();
+        let block: Vec<Cell> = (0..6).flat_map(|row| (0..6).map(move |col| (row, col))).collect();
+        let mut seed = block.clone();
+        seed.extend(block.iter().map(|&(row, col)| (row + 1_000_000_000_000, col + 1_000_000_000_000)));
+
+        let mut engine = LtlEngine::new(seed, rule);
+        engine.step();
+        let cells = engine.cells();
+        assert!(cells.iter().any(|&(row, _)| row < 100) && cells.iter().any(|&(row, _)| row > 100));
+    }
+}
@@ -0,0 +1,168 @@
+//! Splitting a generation into separate objects.
+
+use std::collections::HashSet;
+
+use crate::coordinate::Coordinate;
+use crate::{bounding_box, Cell};
+
+/// Which cells count as part of the same object.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Connectivity {
+    /// Cells touching, diagonals included.
+    #[default]
+    Adjacent,
+    /// Cells at most 2 apart in any direction, so a gap of one dead cell doesn't split an object.
+    /// Objects that close together usually affect each other.
+    Distance2
+}
+
+impl Connectivity {
+    /// Offsets of every cell connected to the cell at the origin.
+    fn offsets(&self) -> Vec<(i128, i128)> {
+        let reach = match self {
+            Connectivity::Adjacent => 1,
+            Connectivity::Distance2 => 2
+        };
+
+        (-reach..=reach)
+            .flat_map(|row| (-reach..=reach).map(move |col| (row, col)))
+            .filter(|&offset| offset != (0, 0))
+            .collect()
+    }
+}
+
+/// One object split out of a generation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Object<C: Coordinate> {
+    /// Cells moved so the bounding box starts at the origin, sorted.
+    pub cells: Vec<Cell<C>>,
+    /// Top left corner of the bounding box where the object was found.
+    pub offset: Cell<C>
+}
+
+impl<C: Coordinate> Object<C> {
+    /// The cells back where they were found.
+    pub fn placed(&self) -> Vec<Cell<C>> {
+        let (top, left) = (self.offset.0.to_i128(), self.offset.1.to_i128());
+        self.cells
+            .iter()
+            .filter_map(|&(row, col)| Some((C::from_i128(top + row.to_i128())?, C::from_i128(left + col.to_i128())?)))
+            .collect()
+    }
+}
+
+/// Splits live cells into objects, largest first and from the top left among equal sizes so the
+/// order doesn't depend on the HashSet.
+///
+/// An object spanning more rows or columns than `C` can count loses the cells that don't fit once
+/// normalized.
+pub fn separate<C: Coordinate>(cells: &HashSet<Cell<C>>, connectivity: Connectivity) -> Vec<Object<C>> {
+    let offsets = connectivity.offsets();
+    let mut unvisited = cells.clone();
+    let mut objects = vec![];
+
+    let mut starts: Vec<Cell<C>> = cells.iter().copied().collect();
+    starts.sort_unstable();
+
+    for start in starts {
+        if !unvisited.remove(&start) {
+            continue;
+        }
+
+        let mut component = vec![start];
+        let mut next = 0;
+        while let Some(&(row, col)) = component.get(next) {
+            next += 1;
+            for &(row_offset, col_offset) in &offsets {
+                let neighbor = (
+                    C::from_i128(row.to_i128() + row_offset),
+                    C::from_i128(col.to_i128() + col_offset)
+                );
+                if let (Some(neighbor_row), Some(neighbor_col)) = neighbor {
+                    if unvisited.remove(&(neighbor_row, neighbor_col)) {
+                        component.push((neighbor_row, neighbor_col));
+                    }
+                }
+            }
+        }
+
+        objects.push(normalize(component));
+    }
+
+    objects.sort_by(|a, b| b.cells.len().cmp(&a.cells.len()).then(a.offset.cmp(&b.offset)));
+    objects
+}
+
+fn normalize<C: Coordinate>(cells: Vec<Cell<C>>) -> Object<C> {
+    let offset = bounding_box(&cells).map_or((C::ZERO, C::ZERO), |(top_left, _)| top_left);
+    let (top, left) = (offset.0.to_i128(), offset.1.to_i128());
+
+    let mut cells: Vec<Cell<C>> = cells
+        .into_iter()
+        .filter_map(|(row, col)| Some((C::from_i128(row.to_i128() - top)?, C::from_i128(col.to_i128() - left)?)))
+        .collect();
+    cells.sort_unstable();
+
+    Object { cells, offset }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{cells, GLIDER};
+
+    const BLOCK: [(i128, i128); 4] = [(0, 0), (0, 1), (1, 0), (1, 1)];
+
+    /// Blocks with their top left corners at each of `corners`.
+    fn blocks(corners: &[(i128, i128)]) -> HashSet<Cell> {
+        let live: Vec<(i128, i128)> = corners
+            .iter()
+            .flat_map(|&(top, left)| BLOCK.iter().map(move |&(row, col)| (top + row, left + col)))
+            .collect();
+        cells(&live)
+    }
+
+    fn sizes(objects: &[Object<i64>]) -> Vec<usize> {
+        objects.iter().map(|object| object.cells.len()).collect()
+    }
+
+    #[test]
+    fn bi_block_splits_only_when_adjacent() {
+        // Two blocks one column apart, a pseudo still life made of two still lifes
+        let bi_block = blocks(&[(0, 0), (0, 3)]);
+
+        let objects = separate(&bi_block, Connectivity::Adjacent);
+        assert_eq!(sizes(&objects), vec![4, 4]);
+        assert_eq!(objects[0].offset, (0, 0));
+        assert_eq!(objects[1].offset, (0, 3));
+        assert!(objects.iter().all(|object| object.cells == vec![(0, 0), (0, 1), (1, 0), (1, 1)]));
+
+        let objects = separate(&bi_block, Connectivity::Distance2);
+        assert_eq!(sizes(&objects), vec![8]);
+        assert_eq!(objects[0].placed().into_iter().collect::<HashSet<_>>(), bi_block);
+    }
+
+    #[test]
+    fn distance_2_stops_at_two_dead_cells() {
+        let apart = blocks(&[(0, 0), (0, 4), (4, 4)]);
+        assert_eq!(sizes(&separate(&apart, Connectivity::Adjacent)), vec![4, 4, 4]);
+        assert_eq!(sizes(&separate(&apart, Connectivity::Distance2)), vec![4, 4, 4]);
+
+        // One cell apart diagonally is still within distance 2
+        let diagonal = blocks(&[(0, 0), (3, 3)]);
+        assert_eq!(sizes(&separate(&diagonal, Connectivity::Adjacent)), vec![4, 4]);
+        assert_eq!(sizes(&separate(&diagonal, Connectivity::Distance2)), vec![8]);
+    }
+
+    #[test]
+    fn diagonal_neighbors_are_adjacent() {
+        // The glider's cells only touch diagonally in places
+        let mut live: HashSet<Cell> = cells(&GLIDER);
+        live.extend(blocks(&[(-10, -10)]));
+
+        let objects = separate(&live, Connectivity::Adjacent);
+        assert_eq!(sizes(&objects), vec![5, 4]);
+        assert_eq!(objects[0].offset, (0, 0));
+        assert_eq!(objects[1].offset, (-10, -10));
+    }
+}
@@ -0,0 +1,154 @@
+//! Life 1.05 patterns.
+//!
+//! After the `#Life 1.05` header come `#D` description lines, the rule as either `#N` for
+//! Conway's Life or `#R 23/3`, then one or more `#P x y` blocks each followed by rows of `.` and
+//! `*` placed with their top left corner at `x y`.
+
+use crate::coordinate::Coordinate;
+use crate::rule::{AnyRule, Rule};
+
+use super::{to_cell, write_grid, Pattern, PatternError, PatternErrorKind};
+
+pub(crate) const HEADER: &str = "#Life 1.05";
+
+pub fn parse<C: Coordinate>(input: &str) -> Result<Pattern<C>, PatternError> {
+    let mut pattern = Pattern::new(vec![]);
+    let mut seen_header = false;
+    // Top left of the current block and the row within it
+    let mut block = (0, 0);
+    let mut row: i128 = 0;
+
+    for (index, line) in input.lines().enumerate() {
+        let line_number = index + 1;
+        let trimmed = line.trim();
+
+        if !seen_header {
+            if trimmed.is_empty() {
+                continue;
+            }
+            if !trimmed.starts_with(HEADER) {
+                return Err(PatternError::new(line_number, 1, PatternErrorKind::MissingHeader));
+            }
+            seen_header = true;
+            continue;
+        }
+
+        if let Some(directive) = trimmed.strip_prefix('#') {
+            let mut chars = directive.chars();
+            let tag = chars.next();
+            let text = chars.as_str().trim();
+            // Where the text after the tag starts, for errors
+            let column = line.trim_end().len() - text.len() + 1;
+
+            match tag {
+                Some('D') => pattern.comments.push(text.to_string()),
+                Some('N') => pattern.rule = Some(AnyRule::Life(Rule::conway())),
+                Some('R') => {
+                    let rule = text.parse::<AnyRule>().map_err(|err| {
+                        PatternError::new(line_number, column, PatternErrorKind::InvalidRule(err))
+                    })?;
+                    pattern.rule = Some(rule);
+                }
+                Some('P') => {
+                    let values: Result<Vec<i128>, _> = text.split_whitespace().map(str::parse).collect();
+                    match values.as_deref() {
+                        Ok([x, y]) => block = (*y, *x),
+                        _ => return Err(PatternError::new(line_number, column, PatternErrorKind::InvalidCoordinates))
+                    }
+                    row = 0;
+                }
+                _ => {}
+            }
+            continue;
+        }
+
+        let (top, left) = block;
+        for (col, ch) in line.trim_end().chars().enumerate() {
+            let error = |kind| PatternError::new(line_number, col + 1, kind);
+            match ch {
+                '*' => {
+                    let cell = to_cell(top + row, left + col as i128)
+                        .ok_or_else(|| error(PatternErrorKind::CoordinateOverflow))?;
+                    pattern.cells.push(cell);
+                }
+                '.' => {}
+                ch => return Err(error(PatternErrorKind::UnexpectedCharacter(ch)))
+            }
+        }
+
+        row += 1;
+    }
+
+    if !seen_header {
+        return Err(PatternError::new(1, 1, PatternErrorKind::MissingHeader));
+    }
+
+    Ok(pattern)
+}
+
+/// Name, author and comments all become `#D` lines and the cells are written as a single block.
+pub fn write<C: Coordinate>(pattern: &Pattern<C>) -> String {
+    let mut out = format!("{}\n", HEADER);
+
+    let descriptions = pattern.name.iter().chain(pattern.author.iter()).chain(pattern.comments.iter());
+    for description in descriptions {
+        out.push_str(&format!("#D {}\n", description));
+    }
+
+    // Rules with no legacy form are written the way they are anywhere else
+    match &pattern.rule {
+        Some(AnyRule::Life(rule)) if *rule != Rule::conway() => {
+            out.push_str(&format!("#R {}\n", rule.to_legacy_string()))
+        }
+        Some(AnyRule::Life(_)) | None => out.push_str("#N\n"),
+        Some(rule) => out.push_str(&format!("#R {}\n", rule))
+    }
+
+    let ((top, left), rows) = write_grid(&pattern.cells, '.', '*');
+    if !rows.is_empty() {
+        out.push_str(&format!("#P {} {}\n", left, top));
+    }
+    for row in rows {
+        out.push_str(&row);
+        out.push('\n');
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips() {
+        let mut pattern = Pattern::new(vec![(-3, 4), (-2, 5), (-1, 3), (-1, 4), (-1, 5)]);
+        pattern.comments = vec!["A glider".to_string(), "moving southeast".to_string()];
+        pattern.rule = Some("B36/S23".parse().unwrap());
+
+        let written = write::<i64>(&pattern);
+        assert_eq!(written, "#Life 1.05\n#D A glider\n#D moving southeast\n#R 23/36\n#P 3 -3\n.*\n..*\n***\n");
+        assert_eq!(parse::<i64>(&written), Ok(pattern));
+    }
+
+    #[test]
+    fn blocks() {
+        let pattern = parse::<i64>("#Life 1.05\n#N\n#P -1 -1\n**\n*.\n#P 5 0\n.*\n").unwrap();
+        assert_eq!(pattern.rule, Some(AnyRule::Life(Rule::conway())));
+        assert_eq!(pattern.cells, vec![(-1, -1), (-1, 0), (0, -1), (0, 6)]);
+    }
+
+    #[test]
+    fn errors_are_positioned() {
+        let error = |input: &str| parse::<i64>(input).map(|_| ()).map_err(|err| (err.line, err.column, err.kind));
+
+        assert_eq!(error("..*\n"), Err((1, 1, PatternErrorKind::MissingHeader)));
+        assert_eq!(error("#Life 1.05\n#P 1\n"), Err((2, 4, PatternErrorKind::InvalidCoordinates)));
+        assert_eq!(error("#Life 1.05\n  #P   1 x\n"), Err((2, 8, PatternErrorKind::InvalidCoordinates)));
+        assert_eq!(
+            error("#Life 1.05\n#R    B3/Q\n"),
+            Err((2, 7, PatternErrorKind::InvalidRule("B3/Q".parse::<AnyRule>().unwrap_err())))
+        );
+        assert_eq!(error("#Life 1.05\n#P 0 0\n.*o\n"), Err((3, 3, PatternErrorKind::UnexpectedCharacter('o'))));
+    }
+}
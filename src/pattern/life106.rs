@@ -0,0 +1,103 @@
+//! Life 1.06 patterns, a `#Life 1.06` header followed by one `x y` pair per live cell.
+
+use crate::coordinate::Coordinate;
+
+use super::{to_cell, Pattern, PatternError, PatternErrorKind};
+
+pub(crate) const HEADER: &str = "#Life 1.06";
+
+/// Coordinates are absolute so cells keep their position on the plane.
+pub fn parse<C: Coordinate>(input: &str) -> Result<Pattern<C>, PatternError> {
+    let mut pattern = Pattern::new(vec![]);
+    let mut seen_header = false;
+
+    for (index, line) in input.lines().enumerate() {
+        let line_number = index + 1;
+        let trimmed = line.trim();
+        if trimmed.is_empty() {
+            continue;
+        }
+
+        if !seen_header {
+            if !trimmed.starts_with(HEADER) {
+                return Err(PatternError::new(line_number, 1, PatternErrorKind::MissingHeader));
+            }
+            seen_header = true;
+            continue;
+        }
+
+        // Files in the wild carry `#D` or `#C` descriptions after the header like Life 1.05,
+        // other `#` lines are skipped
+        if let Some(directive) = trimmed.strip_prefix('#') {
+            let mut chars = directive.chars();
+            if let Some('D') | Some('C') = chars.next() {
+                pattern.comments.push(chars.as_str().trim().to_string());
+            }
+            continue;
+        }
+
+        let column = line.len() - line.trim_start().len() + 1;
+        let error = |kind| PatternError::new(line_number, column, kind);
+
+        let values: Result<Vec<i128>, _> = trimmed.split_whitespace().map(str::parse).collect();
+        let (x, y) = match values.as_deref() {
+            Ok([x, y]) => (*x, *y),
+            _ => return Err(error(PatternErrorKind::InvalidCoordinates))
+        };
+
+        let cell = to_cell(y, x).ok_or_else(|| error(PatternErrorKind::CoordinateOverflow))?;
+        pattern.cells.push(cell);
+    }
+
+    if !seen_header {
+        return Err(PatternError::new(1, 1, PatternErrorKind::MissingHeader));
+    }
+
+    Ok(pattern)
+}
+
+/// Name, author and comments become `#D` lines as in Life 1.05, the format has nowhere to put
+/// the rule.
+pub fn write<C: Coordinate>(pattern: &Pattern<C>) -> String {
+    let mut cells = pattern.cells.clone();
+    cells.sort_unstable();
+    cells.dedup();
+
+    let mut out = format!("{}\n", HEADER);
+    let descriptions = pattern.name.iter().chain(pattern.author.iter()).chain(pattern.comments.iter());
+    for description in descriptions {
+        out.push_str(&format!("#D {}\n", description));
+    }
+    for (row, col) in cells {
+        out.push_str(&format!("{} {}\n", col, row));
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn comments_after_header() {
+        let pattern = parse::<i64>("#Life 1.06\n#D A glider\n#N\n0 -1\n1 0\n#C moving southeast\n-1 1\n0 1\n1 1\n").unwrap();
+        assert_eq!(pattern.comments, vec!["A glider".to_string(), "moving southeast".to_string()]);
+        assert_eq!(pattern.cells, vec![(-1, 0), (0, 1), (1, -1), (1, 0), (1, 1)]);
+    }
+
+    #[test]
+    fn descriptions_are_written() {
+        let mut pattern = Pattern::new(vec![(-1, 0), (0, 1), (1, -1), (1, 0), (1, 1)]);
+        pattern.name = Some("Glider".to_string());
+        pattern.author = Some("Richard K. Guy".to_string());
+        pattern.comments.push("moving southeast".to_string());
+
+        let written = write::<i64>(&pattern);
+        assert_eq!(written, "#Life 1.06\n#D Glider\n#D Richard K. Guy\n#D moving southeast\n0 -1\n1 0\n-1 1\n0 1\n1 1\n");
+
+        let read = parse::<i64>(&written).unwrap();
+        assert_eq!(read.comments, vec!["Glider", "Richard K. Guy", "moving southeast"]);
+        assert_eq!(read.cells, pattern.cells);
+    }
+}
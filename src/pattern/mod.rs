@@ -0,0 +1,236 @@
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::fmt;
+use std::path::Path;
+
+use crate::coordinate::Coordinate;
+use crate::rule::{AnyRule, RuleParseError};
+use crate::{bounding_box, Cell, CellCoordinate};
+
+pub mod life105;
+pub mod life106;
+pub mod plaintext;
+pub mod rle;
+
+/// A seed read from a pattern file along with whatever metadata the file carried.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pattern<C: Coordinate = CellCoordinate> {
+    pub name: Option<String>,
+    pub author: Option<String>,
+    pub comments: Vec<String>,
+    /// Rule the pattern was designed for, None if the file didn't say.
+    pub rule: Option<AnyRule>,
+    pub cells: Vec<Cell<C>>,
+    /// States of the cells in `cells` that are in some state other than alive, from RLE files
+    /// for rules with more than 2 states. Every other format only has live cells.
+    pub states: HashMap<Cell<C>, u8>
+}
+
+impl<C: Coordinate> Pattern<C> {
+    pub fn new(cells: Vec<Cell<C>>) -> Pattern<C> {
+        Pattern {
+            name: None,
+            author: None,
+            comments: vec![],
+            rule: None,
+            cells,
+            states: HashMap::new()
+        }
+    }
+
+    /// Captures a generation yielded by `GOLGenerationIterator` along with the rule it ran under.
+    pub fn from_generation(generation: &HashSet<Cell<C>>, rule: impl Into<AnyRule>) -> Pattern<C> {
+        let mut pattern = Pattern::new(generation.iter().copied().collect());
+        pattern.rule = Some(rule.into());
+        pattern
+    }
+
+    /// Live cells ready to hand to `GOLGenerationIterator::new`.
+    pub fn into_seed(self) -> Vec<Cell<C>> {
+        self.cells
+    }
+
+    /// The state of every cell ready to hand to `GOLGenerationIterator::with_states`.
+    pub fn into_states(self) -> HashMap<Cell<C>, u8> {
+        let states = self.states;
+        self.cells.into_iter().map(|cell| (cell, states.get(&cell).copied().unwrap_or(1))).collect()
+    }
+}
+
+/// File formats patterns can be read from and written to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// Run Length Encoded, `.rle`
+    Rle,
+    /// Grid of `.` and `O` with `!` comments, `.cells`
+    Plaintext,
+    /// `#P` blocks of `.` and `*`, `.lif`
+    Life105,
+    /// One `x y` pair per live cell, `.lif`
+    Life106
+}
+
+impl Format {
+    /// Format implied by a file extension. `.lif` and `.life` are shared by both Life versions and
+    /// map to the simpler 1.06.
+    pub fn from_path(path: &Path) -> Option<Format> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "rle" => Some(Format::Rle),
+            "cells" => Some(Format::Plaintext),
+            "lif" | "life" => Some(Format::Life106),
+            _ => None
+        }
+    }
+
+    /// Works out the format of `content`, a `#Life` header always wins since the extension can't
+    /// tell the two Life versions apart. Without a header or a known extension the first
+    /// meaningful line is used to choose between plaintext and RLE.
+    pub fn detect(path: Option<&Path>, content: &str) -> Format {
+        let first_line = content.lines().map(str::trim).find(|line| !line.is_empty()).unwrap_or("");
+        if first_line.starts_with(life105::HEADER) {
+            return Format::Life105;
+        }
+        if first_line.starts_with(life106::HEADER) {
+            return Format::Life106;
+        }
+
+        if let Some(format) = path.and_then(Format::from_path) {
+            if format != Format::Life106 {
+                return format;
+            }
+        }
+
+        let looks_like_grid = first_line.starts_with('!')
+            || first_line.chars().all(|ch| plaintext::DEAD.contains(&ch) || plaintext::ALIVE.contains(&ch));
+        if looks_like_grid {
+            Format::Plaintext
+        } else {
+            Format::Rle
+        }
+    }
+}
+
+pub fn parse<C: Coordinate>(input: &str, format: Format) -> Result<Pattern<C>, PatternError> {
+    match format {
+        Format::Rle => rle::parse(input),
+        Format::Plaintext => plaintext::parse(input),
+        Format::Life105 => life105::parse(input),
+        Format::Life106 => life106::parse(input)
+    }
+}
+
+pub fn write<C: Coordinate>(pattern: &Pattern<C>, format: Format) -> String {
+    match format {
+        Format::Rle => rle::write(pattern),
+        Format::Plaintext => plaintext::write(pattern),
+        Format::Life105 => life105::write(pattern),
+        Format::Life106 => life106::write(pattern)
+    }
+}
+
+/// Where in the file parsing failed, both line and column start at 1.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PatternError {
+    pub line: usize,
+    pub column: usize,
+    pub kind: PatternErrorKind
+}
+
+impl PatternError {
+    fn new(line: usize, column: usize, kind: PatternErrorKind) -> PatternError {
+        PatternError { line, column, kind }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PatternErrorKind {
+    /// The file ended before the size header was found.
+    MissingHeader,
+    /// The header line couldn't be understood, holds what was wrong with it.
+    InvalidHeader(String),
+    InvalidRule(RuleParseError),
+    UnexpectedCharacter(char),
+    /// A cell or offset doesn't fit in the Coordinate type it is being read into.
+    CoordinateOverflow,
+    /// Cells were found outside of the width and height the header declared.
+    OutOfBounds,
+    /// A line that should hold a pair of integer coordinates didn't.
+    InvalidCoordinates
+}
+
+impl fmt::Display for PatternError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}, column {}: ", self.line, self.column)?;
+        match &self.kind {
+            PatternErrorKind::MissingHeader => write!(f, "missing header line"),
+            PatternErrorKind::InvalidHeader(reason) => write!(f, "invalid header, {}", reason),
+            PatternErrorKind::InvalidRule(err) => write!(f, "invalid rule, {}", err),
+            PatternErrorKind::UnexpectedCharacter(ch) => write!(f, "unexpected character '{}'", ch),
+            PatternErrorKind::CoordinateOverflow => write!(f, "cell is outside the coordinate range"),
+            PatternErrorKind::OutOfBounds => write!(f, "cell is outside the size given in the header"),
+            PatternErrorKind::InvalidCoordinates => write!(f, "expected two integer coordinates")
+        }
+    }
+}
+
+impl Error for PatternError {}
+
+/// Converts a cell relative to the pattern's top left into the caller's Coordinate type.
+fn to_cell<C: Coordinate>(row: i128, col: i128) -> Option<Cell<C>> {
+    Some((C::from_i128(row)?, C::from_i128(col)?))
+}
+
+/// Lays the cells out as text rows relative to their bounding box, trailing dead cells are left
+/// off and empty rows are a single dead cell. Returns the (row, col) of the top left corner along
+/// with the rows.
+fn write_grid<C: Coordinate>(cells: &[Cell<C>], dead: char, alive: char) -> ((i128, i128), Vec<String>) {
+    let ((top, left), (bottom, _)) = match bounding_box(cells) {
+        Some(((top, left), (bottom, right))) => {
+            ((top.to_i128(), left.to_i128()), (bottom.to_i128(), right.to_i128()))
+        }
+        None => return ((0, 0), vec![])
+    };
+
+    let mut rows: Vec<Vec<char>> = vec![vec![]; (bottom - top + 1) as usize];
+    for &(row, col) in cells {
+        let line = &mut rows[(row.to_i128() - top) as usize];
+        let col = (col.to_i128() - left) as usize;
+        if line.len() <= col {
+            line.resize(col + 1, dead);
+        }
+        line[col] = alive;
+    }
+
+    let rows = rows
+        .into_iter()
+        .map(|line| if line.is_empty() { dead.to_string() } else { line.into_iter().collect() })
+        .collect();
+
+    ((top, left), rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detect() {
+        let path = |name: &'static str| Some(Path::new(name));
+
+        // A header wins over the extension
+        assert_eq!(Format::detect(path("glider.rle"), "\n#Life 1.05\n#P 0 0\n.*\n"), Format::Life105);
+        assert_eq!(Format::detect(path("glider.cells"), "#Life 1.06\n0 0\n"), Format::Life106);
+        assert_eq!(Format::detect(None, "#Life 1.06\n0 0\n"), Format::Life106);
+
+        assert_eq!(Format::detect(path("glider.RLE"), "!Name: not really\n"), Format::Rle);
+        assert_eq!(Format::detect(path("glider.cells"), "x = 3, y = 3\n"), Format::Plaintext);
+
+        // `.lif` without a header and no extension at all go by the first line
+        assert_eq!(Format::detect(path("glider.lif"), "x = 3, y = 3\nbo$2bo$3o!\n"), Format::Rle);
+        assert_eq!(Format::detect(None, "!Name: Glider\n.O\n"), Format::Plaintext);
+        assert_eq!(Format::detect(None, "\n  .O.\n"), Format::Plaintext);
+        assert_eq!(Format::detect(None, "#N Glider\nx = 3, y = 3\n"), Format::Rle);
+        assert_eq!(Format::detect(None, ""), Format::Plaintext);
+    }
+}
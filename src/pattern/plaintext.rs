@@ -0,0 +1,101 @@
+//! Plaintext `.cells` patterns.
+//!
+//! Lines starting with `!` are comments, `!Name:` and `!Author:` carry metadata, every other line
+//! is a row of the pattern where `.` is a dead cell and `O` a live one.
+
+use crate::coordinate::Coordinate;
+
+use super::{to_cell, write_grid, Pattern, PatternError, PatternErrorKind};
+
+pub(crate) const DEAD: [char; 1] = ['.'];
+/// `*` isn't part of the format but older files use it for live cells.
+pub(crate) const ALIVE: [char; 2] = ['O', '*'];
+
+/// Cells come out relative to the top left of the grid, the format has no way to place them.
+pub fn parse<C: Coordinate>(input: &str) -> Result<Pattern<C>, PatternError> {
+    let mut pattern = Pattern::new(vec![]);
+    let mut row: i128 = 0;
+
+    for (index, line) in input.lines().enumerate() {
+        let line_number = index + 1;
+        if let Some(comment) = line.strip_prefix('!') {
+            if let Some(name) = comment.strip_prefix("Name:") {
+                pattern.name = Some(name.trim().to_string());
+            } else if let Some(author) = comment.strip_prefix("Author:") {
+                pattern.author = Some(author.trim().to_string());
+            } else {
+                pattern.comments.push(comment.trim().to_string());
+            }
+            continue;
+        }
+
+        for (col, ch) in line.trim_end().chars().enumerate() {
+            let error = |kind| PatternError::new(line_number, col + 1, kind);
+            if ALIVE.contains(&ch) {
+                let cell = to_cell(row, col as i128).ok_or_else(|| error(PatternErrorKind::CoordinateOverflow))?;
+                pattern.cells.push(cell);
+            } else if !DEAD.contains(&ch) {
+                return Err(error(PatternErrorKind::UnexpectedCharacter(ch)));
+            }
+        }
+
+        row += 1;
+    }
+
+    Ok(pattern)
+}
+
+/// The grid starts at the pattern's bounding box, where it sat on the plane is not kept.
+pub fn write<C: Coordinate>(pattern: &Pattern<C>) -> String {
+    let mut out = String::new();
+
+    if let Some(name) = &pattern.name {
+        out.push_str(&format!("!Name: {}\n", name));
+    }
+    if let Some(author) = &pattern.author {
+        out.push_str(&format!("!Author: {}\n", author));
+    }
+    for comment in &pattern.comments {
+        out.push_str(&format!("!{}\n", comment));
+    }
+
+    let (_, rows) = write_grid(&pattern.cells, DEAD[0], ALIVE[0]);
+    for row in rows {
+        out.push_str(&row);
+        out.push('\n');
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips() {
+        let mut pattern = Pattern::new(vec![(0, 1), (1, 2), (2, 0), (2, 1), (2, 2)]);
+        pattern.name = Some("Glider".to_string());
+        pattern.author = Some("Richard K. Guy".to_string());
+        pattern.comments = vec!["The smallest spaceship".to_string()];
+
+        let written = write::<i64>(&pattern);
+        assert_eq!(written, "!Name: Glider\n!Author: Richard K. Guy\n!The smallest spaceship\n.O\n..O\nOOO\n");
+        assert_eq!(parse::<i64>(&written), Ok(pattern));
+    }
+
+    #[test]
+    fn written_from_the_bounding_box() {
+        let pattern = Pattern::new(vec![(-5, 7), (-3, 8)]);
+        assert_eq!(write::<i64>(&pattern), "O\n.\n.O\n");
+        assert_eq!(parse::<i64>("O\n.\n.O\n").unwrap().cells, vec![(0, 0), (2, 1)]);
+    }
+
+    #[test]
+    fn old_style_and_errors() {
+        assert_eq!(parse::<i64>("*.*\n").unwrap().cells, vec![(0, 0), (0, 2)]);
+
+        let err = parse::<i64>("!Name: x\n.O\n.Ox\n").unwrap_err();
+        assert_eq!((err.line, err.column, err.kind), (3, 3, PatternErrorKind::UnexpectedCharacter('x')));
+    }
+}
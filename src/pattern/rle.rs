@@ -0,0 +1,415 @@
+//! Run Length Encoded `.rle` patterns.
+//!
+//! A file is made of optional `#` comment lines, a header giving the size and rule
+//! (`x = 3, y = 3, rule = B3/S23`) and a body of runs such as `bo$2bo$3o!` where `b` is a dead
+//! cell, `o` a live one, `$` ends a row and `!` ends the pattern.
+//!
+//! Rules with more than 2 states write `.` for state 0 and `A` to `X` for states 1 to 24, higher
+//! states put one of `p` to `y` in front for each further 24, `pA` is 25 and `yO` is 255.
+//!
+//! Both reading and writing are supported so evolved generations can be shared with other tools.
+
+use crate::bounding_box;
+use crate::coordinate::Coordinate;
+use crate::rule::AnyRule;
+
+use super::{to_cell, Pattern, PatternError, PatternErrorKind};
+
+/// Parses an RLE file, cells come out relative to the top left of the pattern unless the file
+/// has a `#P`/`#R` line moving it.
+pub fn parse<C: Coordinate>(input: &str) -> Result<Pattern<C>, PatternError> {
+    let mut pattern = Pattern::new(vec![]);
+    let mut offset = (0, 0);
+    let mut size = None;
+    let mut last_line = 0;
+
+    let mut lines = input.lines().enumerate().map(|(index, line)| (index + 1, line));
+    for (line_number, line) in lines.by_ref() {
+        last_line = line_number;
+        let trimmed = line.trim();
+        if trimmed.is_empty() {
+            continue;
+        }
+
+        if trimmed.starts_with('#') {
+            parse_comment(line_number, line, &mut pattern, &mut offset)?;
+            continue;
+        }
+
+        size = Some(parse_header(line_number, line, &mut pattern)?);
+        break;
+    }
+
+    let (width, height) = match size {
+        Some(size) => size,
+        None => return Err(PatternError::new(last_line + 1, 1, PatternErrorKind::MissingHeader))
+    };
+
+    let (row_offset, col_offset) = offset;
+    let mut row: i128 = 0;
+    let mut col: i128 = 0;
+    let mut count: Option<i128> = None;
+    // States past 24 from a `p` to `y` waiting for their letter, along with the error pointing at
+    // the prefix if no letter follows
+    let mut high_states: Option<(u32, PatternError)> = None;
+
+    for (line_number, line) in lines {
+        for (index, ch) in line.chars().enumerate() {
+            let error = |kind| PatternError::new(line_number, index + 1, kind);
+
+            if let Some((_, prefix_error)) = &high_states {
+                if !matches!(ch, 'A'..='X') {
+                    return Err(prefix_error.clone());
+                }
+            }
+
+            if let Some(digit) = ch.to_digit(10) {
+                let run = count
+                    .unwrap_or(0)
+                    .checked_mul(10)
+                    .and_then(|run| run.checked_add(digit as i128))
+                    .ok_or_else(|| error(PatternErrorKind::CoordinateOverflow))?;
+                count = Some(run);
+                continue;
+            }
+
+            // Whitespace can show up anywhere in the body, even between a count and its tag
+            if ch.is_whitespace() {
+                continue;
+            }
+
+            if let 'p'..='y' = ch {
+                high_states = Some(((ch as u32 - 'p' as u32 + 1) * 24, error(PatternErrorKind::UnexpectedCharacter(ch))));
+                continue;
+            }
+
+            let run = count.take().unwrap_or(1);
+            let state = match ch {
+                'o' => 1,
+                'A'..='X' => high_states.take().map_or(0, |(states, _)| states) + (ch as u32 - 'A' as u32 + 1),
+                _ => 0
+            };
+            match ch {
+                'b' | '.' => col += run,
+                'o' | 'A'..='X' => {
+                    if row >= height || col + run > width {
+                        return Err(error(PatternErrorKind::OutOfBounds));
+                    }
+                    if state > u8::MAX as u32 {
+                        return Err(error(PatternErrorKind::UnexpectedCharacter(ch)));
+                    }
+
+                    for _ in 0..run {
+                        let cell = to_cell(row + row_offset, col + col_offset)
+                            .ok_or_else(|| error(PatternErrorKind::CoordinateOverflow))?;
+                        pattern.cells.push(cell);
+                        if state > 1 {
+                            pattern.states.insert(cell, state as u8);
+                        }
+                        col += 1;
+                    }
+                }
+                '$' => {
+                    row += run;
+                    col = 0;
+                }
+                '!' => return Ok(pattern),
+                ch => return Err(error(PatternErrorKind::UnexpectedCharacter(ch)))
+            }
+        }
+    }
+
+    if let Some((_, prefix_error)) = high_states {
+        return Err(prefix_error);
+    }
+
+    // Plenty of files in the wild are missing the final `!`, everything up to the end is kept
+    Ok(pattern)
+}
+
+fn parse_comment<C: Coordinate>(
+    line_number: usize,
+    line: &str,
+    pattern: &mut Pattern<C>,
+    offset: &mut (i128, i128)
+) -> Result<(), PatternError> {
+    let trimmed = line.trim_start();
+    let mut chars = trimmed.chars();
+    chars.next();
+    let tag = chars.next();
+    let rest = chars.as_str();
+    let text = rest.trim();
+    // Where the text after the tag starts, for errors
+    let column = line.len() - rest.trim_start().len() + 1;
+
+    match tag {
+        Some('N') => pattern.name = Some(text.to_string()),
+        Some('O') => pattern.author = Some(text.to_string()),
+        Some('C') | Some('c') => pattern.comments.push(text.to_string()),
+        Some('r') => {
+            let rule = text.parse::<AnyRule>().map_err(|err| {
+                PatternError::new(line_number, column, PatternErrorKind::InvalidRule(err))
+            })?;
+            pattern.rule = Some(rule);
+        }
+        Some('P') | Some('R') => {
+            // `#P x y` is the column then row of the top left corner
+            let values: Result<Vec<i128>, _> = text.split_whitespace().map(str::parse).collect();
+            match values.as_deref() {
+                Ok([x, y]) => *offset = (*y, *x),
+                _ => {
+                    let kind = PatternErrorKind::InvalidHeader("offset must be two integers".to_string());
+                    return Err(PatternError::new(line_number, column, kind));
+                }
+            }
+        }
+        // Other comment types carry nothing the engine uses
+        _ => {}
+    }
+
+    Ok(())
+}
+
+/// Returns the declared (width, height) and stores the rule if one is given.
+fn parse_header<C: Coordinate>(
+    line_number: usize,
+    line: &str,
+    pattern: &mut Pattern<C>
+) -> Result<(i128, i128), PatternError> {
+    let mut width = None;
+    let mut height = None;
+    let mut start = 0;
+
+    for part in line.split(',') {
+        let part_start = start;
+        let column = start + (part.len() - part.trim_start().len()) + 1;
+        start += part.len() + 1;
+        let error = |reason: &str| {
+            PatternError::new(line_number, column, PatternErrorKind::InvalidHeader(reason.to_string()))
+        };
+
+        let mut key_value = part.splitn(2, '=');
+        let key = key_value.next().unwrap_or("").trim();
+        let value = match key_value.next() {
+            Some(value) => value,
+            None => return Err(error("expected 'key = value'"))
+        };
+        let value_column = column + part.trim_start().len() - value.trim_start().len();
+        let value = value.trim();
+
+        match key {
+            "x" => width = Some(value.parse::<u64>().map_err(|_| error("x must be a positive integer"))?),
+            "y" => height = Some(value.parse::<u64>().map_err(|_| error("y must be a positive integer"))?),
+            "rule" => {
+                // Larger than Life rules have commas of their own so the rule runs to the end of
+                // the line
+                let value = line[part_start..].split_once('=').map_or(value, |(_, rule)| rule).trim();
+                let rule = value.parse::<AnyRule>().map_err(|err| {
+                    PatternError::new(line_number, value_column, PatternErrorKind::InvalidRule(err))
+                })?;
+                pattern.rule = Some(rule);
+                break;
+            }
+            _ => return Err(error(&format!("unknown key '{}'", key)))
+        }
+    }
+
+    match (width, height) {
+        (Some(width), Some(height)) => Ok((width as i128, height as i128)),
+        _ => Err(PatternError::new(
+            line_number,
+            1,
+            PatternErrorKind::InvalidHeader("both x and y are required".to_string())
+        ))
+    }
+}
+
+/// Lines in the body are wrapped to stay within this many characters.
+const MAX_LINE_LENGTH: usize = 70;
+
+/// Writes a pattern out as RLE.
+///
+/// The header size is the bounding box of the cells and a `#R` line records its top left corner
+/// when it isn't at the origin, so reading the output back gives the same cells. Patterns without
+/// a rule are written as Conway's Life. Patterns with cells in states other than alive use the
+/// letters for each state.
+pub fn write<C: Coordinate>(pattern: &Pattern<C>) -> String {
+    let mut out = String::new();
+
+    if let Some(name) = &pattern.name {
+        out.push_str(&format!("#N {}\n", name));
+    }
+    if let Some(author) = &pattern.author {
+        out.push_str(&format!("#O {}\n", author));
+    }
+    for comment in &pattern.comments {
+        out.push_str(&format!("#C {}\n", comment));
+    }
+
+    let mut cells: Vec<((i128, i128), u8)> = pattern.cells
+        .iter()
+        .map(|cell| {
+            let state = pattern.states.get(cell).copied().unwrap_or(1);
+            ((cell.0.to_i128(), cell.1.to_i128()), state)
+        })
+        .collect();
+    cells.sort_unstable();
+    cells.dedup_by_key(|&mut (cell, _)| cell);
+
+    let multistate = cells.iter().any(|&(_, state)| state > 1);
+    let dead = if multistate { "." } else { "b" };
+    let tag = |state| if multistate { state_tag(state) } else { "o".to_string() };
+
+    let rule = pattern.rule.clone().unwrap_or_default();
+    let ((top, left), (bottom, right)) = match bounding_box(&pattern.cells) {
+        Some(((top, left), (bottom, right))) => {
+            ((top.to_i128(), left.to_i128()), (bottom.to_i128(), right.to_i128()))
+        }
+        None => {
+            out.push_str(&format!("x = 0, y = 0, rule = {}\n!\n", rule));
+            return out;
+        }
+    };
+
+    if top != 0 || left != 0 {
+        out.push_str(&format!("#R {} {}\n", left, top));
+    }
+    out.push_str(&format!("x = {}, y = {}, rule = {}\n", right - left + 1, bottom - top + 1, rule));
+
+    // Build up whole runs first so a line break never lands between a count and its tag
+    let mut runs = vec![];
+    let mut row = top;
+    let mut col = left;
+    let mut live_run = (1, 0);
+    for &((cell_row, cell_col), state) in &cells {
+        if cell_row != row || cell_col != col || state != live_run.0 {
+            push_run(&mut runs, live_run.1, &tag(live_run.0));
+            live_run = (state, 0);
+        }
+        if cell_row != row {
+            push_run(&mut runs, cell_row - row, "$");
+            row = cell_row;
+            col = left;
+        }
+        if cell_col != col {
+            push_run(&mut runs, cell_col - col, dead);
+        }
+
+        live_run.1 += 1;
+        col = cell_col + 1;
+    }
+    push_run(&mut runs, live_run.1, &tag(live_run.0));
+    runs.push("!".to_string());
+
+    let mut line_length = 0;
+    for run in runs {
+        if line_length + run.len() > MAX_LINE_LENGTH {
+            out.push('\n');
+            line_length = 0;
+        }
+        line_length += run.len();
+        out.push_str(&run);
+    }
+    out.push('\n');
+
+    out
+}
+
+fn push_run(runs: &mut Vec<String>, count: i128, tag: &str) {
+    match count {
+        0 => {}
+        1 => runs.push(tag.to_string()),
+        _ => runs.push(format!("{}{}", count, tag))
+    }
+}
+
+/// Letters for a state above 0 in multi-state RLE.
+fn state_tag(state: u8) -> String {
+    let index = state as u32 - 1;
+    let letter = std::char::from_u32('A' as u32 + index % 24).unwrap_or('A');
+    match index / 24 {
+        0 => letter.to_string(),
+        prefix => format!("{}{}", std::char::from_u32('p' as u32 + prefix - 1).unwrap_or('p'), letter)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+    use crate::rule::RuleParseError;
+
+    fn error_at(input: &str) -> (usize, usize, PatternErrorKind) {
+        let err = parse::<i64>(input).unwrap_err();
+        (err.line, err.column, err.kind)
+    }
+
+    #[test]
+    fn stray_prefix_is_reported_where_it_is() {
+        assert_eq!(error_at("x=3,y=3\nbo$2bo$3oq!"), (2, 10, PatternErrorKind::UnexpectedCharacter('q')));
+        assert_eq!(error_at("x=3,y=1\n.pY!"), (2, 2, PatternErrorKind::UnexpectedCharacter('p')));
+        assert_eq!(error_at("x=3,y=1\n.Ap"), (2, 3, PatternErrorKind::UnexpectedCharacter('p')));
+    }
+
+    #[test]
+    fn multistate_letters() {
+        let pattern = parse::<i64>("x = 6, y = 1, rule = WireWorld\n.AB pA2yO!").unwrap();
+        let states = pattern.clone().into_states();
+        assert_eq!(states.len(), 5);
+        assert_eq!(states[&(0, 1)], 1);
+        assert_eq!(states[&(0, 2)], 2);
+        assert_eq!(states[&(0, 3)], 25);
+        assert_eq!(states[&(0, 5)], 255);
+        assert_eq!(parse::<i64>(&write(&pattern)).unwrap().into_states(), states);
+    }
+
+    #[test]
+    fn errors_are_positioned() {
+        assert_eq!(error_at("#N Glider\n#C no header\n"), (3, 1, PatternErrorKind::MissingHeader));
+        assert_eq!(error_at("x = 2, y = 2\nbo$2bo!"), (2, 6, PatternErrorKind::OutOfBounds));
+        assert_eq!(error_at("x = 1, y = 1\no$o!"), (2, 3, PatternErrorKind::OutOfBounds));
+        assert_eq!(
+            error_at("x = 3, y = 3, rule = B9/S\nbo$2bo$3o!"),
+            (1, 22, PatternErrorKind::InvalidRule(RuleParseError::InvalidNeighborCount('9')))
+        );
+        assert_eq!(
+            error_at("#N Glider\n  #r B3x/S23\nx = 3, y = 3\nbo$2bo$3o!"),
+            (2, 6, PatternErrorKind::InvalidRule(RuleParseError::InvalidLetter(3, 'x')))
+        );
+    }
+
+    #[test]
+    fn offset_round_trips() {
+        let mut pattern = Pattern::new(vec![(-5, 7), (-4, 8), (-3, 6), (-3, 7), (-3, 8)]);
+        pattern.rule = Some("B36/S23".parse().unwrap());
+        let written = write(&pattern);
+        assert!(written.starts_with("#R 6 -5\nx = 3, y = 3, rule = B36/S23\n"), "{}", written);
+
+        let read = parse::<i64>(&written).unwrap();
+        assert_eq!(read.rule, pattern.rule);
+        assert_eq!(read.cells.iter().collect::<HashSet<_>>(), pattern.cells.iter().collect());
+    }
+
+    #[test]
+    fn wrapping_keeps_runs_whole() {
+        // Gaps of 1 to 12 cells make runs of every width, so some must land on the 70th column
+        let mut cells = vec![];
+        let mut col = 0;
+        for gap in (1..=12).cycle().take(200) {
+            cells.push((gap % 3, col));
+            col += gap;
+        }
+        let pattern = Pattern::new(cells);
+        let written = write(&pattern);
+
+        let body: Vec<&str> = written.lines().skip(1).collect();
+        assert!(body.len() > 1);
+        for line in &body {
+            assert!(line.len() <= MAX_LINE_LENGTH, "{}", line);
+            assert!(!line.ends_with(|ch: char| ch.is_ascii_digit()), "{}", line);
+        }
+        let read = parse::<i64>(&written).unwrap();
+        assert_eq!(read.cells.iter().collect::<HashSet<_>>(), pattern.cells.iter().collect());
+    }
+}
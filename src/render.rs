@@ -0,0 +1,209 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::coordinate::Coordinate;
+use crate::{Cell, CellCoordinate};
+
+/// Rectangular window onto the universe, `top` and `left` are the row and column of its top left
+/// cell.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Viewport<C: Coordinate = CellCoordinate> {
+    pub top: C,
+    pub left: C,
+    pub width: usize,
+    pub height: usize
+}
+
+impl<C: Coordinate> Viewport<C> {
+    /// Viewport exactly covering the given corners, None if it is too large to index with usize.
+    pub fn covering((top, left): Cell<C>, (bottom, right): Cell<C>) -> Option<Viewport<C>> {
+        let width = right.to_i128() - left.to_i128() + 1;
+        let height = bottom.to_i128() - top.to_i128() + 1;
+        if width <= 0 || height <= 0 || width > usize::MAX as i128 || height > usize::MAX as i128 {
+            return None;
+        }
+
+        Some(Viewport { top, left, width: width as usize, height: height as usize })
+    }
+
+    /// Cell shown at `row` and `col` of the viewport, None when that spot is past the edge of the
+    /// coordinate range.
+    pub fn cell_at(&self, row: usize, col: usize) -> Option<Cell<C>> {
+        Some((
+            C::from_i128(self.top.to_i128() + row as i128)?,
+            C::from_i128(self.left.to_i128() + col as i128)?
+        ))
+    }
+}
+
+/// Draws the part of a generation inside the viewport, live cells are `x` and dead ones `-`.
+pub fn render_text<C: Coordinate>(generation: &HashSet<Cell<C>>, viewport: &Viewport<C>) -> String {
+    draw_text(viewport, false, |cell| generation.contains(&cell) as u8)
+}
+
+/// Like `render_text` with the dying states of Generations rules drawn as their state number in
+/// base 36, `2` being the first generation after a cell stopped being alive.
+pub fn render_states_text<C: Coordinate>(states: &HashMap<Cell<C>, u8>, viewport: &Viewport<C>) -> String {
+    draw_text(viewport, false, |cell| states.get(&cell).copied().unwrap_or(0))
+}
+
+/// Like `render_text` for rules with the hexagonal neighborhood. Every row is drawn half a cell
+/// to the left of the one above so each cell touches its 6 neighbors on screen.
+pub fn render_hex_text<C: Coordinate>(generation: &HashSet<Cell<C>>, viewport: &Viewport<C>) -> String {
+    draw_text(viewport, true, |cell| generation.contains(&cell) as u8)
+}
+
+/// Like `render_states_text` laid out as `render_hex_text`.
+pub fn render_hex_states_text<C: Coordinate>(states: &HashMap<Cell<C>, u8>, viewport: &Viewport<C>) -> String {
+    draw_text(viewport, true, |cell| states.get(&cell).copied().unwrap_or(0))
+}
+
+fn draw_text<C: Coordinate>(viewport: &Viewport<C>, hexagonal: bool, state: impl Fn(Cell<C>) -> u8) -> String {
+    let mut out = String::with_capacity(viewport.width * viewport.height * 3 + viewport.height);
+
+    for row in 0..viewport.height {
+        // Hexagonal cells are two characters wide so half a cell is one space
+        if hexagonal {
+            out.push_str(&" ".repeat(viewport.height - 1 - row));
+        }
+
+        for col in 0..viewport.width {
+            let state = viewport.cell_at(row, col).map_or(0, &state);
+            out.push(match state {
+                0 => '-',
+                1 => 'x',
+                state => std::char::from_digit(state as u32, 36).unwrap_or('+')
+            });
+            out.push_str(if hexagonal { " " } else { "  " });
+        }
+        out.push('\n');
+    }
+
+    out
+}
+
+/// How much of the universe each character on screen stands for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Zoom {
+    /// Two characters per cell so cells come out roughly square.
+    Wide,
+    /// One character per cell.
+    Normal,
+    /// 1x2 cells per character using half block characters.
+    HalfBlock,
+    /// 2x4 cells per character using braille characters.
+    Braille
+}
+
+impl Zoom {
+    /// The next level further out, Braille stays Braille.
+    pub fn zoom_out(&self) -> Zoom {
+        match self {
+            Zoom::Wide => Zoom::Normal,
+            Zoom::Normal => Zoom::HalfBlock,
+            Zoom::HalfBlock | Zoom::Braille => Zoom::Braille
+        }
+    }
+
+    /// The next level further in, Wide stays Wide.
+    pub fn zoom_in(&self) -> Zoom {
+        match self {
+            Zoom::Wide | Zoom::Normal => Zoom::Wide,
+            Zoom::HalfBlock => Zoom::Normal,
+            Zoom::Braille => Zoom::HalfBlock
+        }
+    }
+
+    /// Number of cells (rows, columns) that fit in `rows` lines of `cols` characters.
+    pub fn cells_for(&self, rows: usize, cols: usize) -> (usize, usize) {
+        match self {
+            Zoom::Wide => (rows, cols / 2),
+            Zoom::Normal => (rows, cols),
+            Zoom::HalfBlock => (rows * 2, cols),
+            Zoom::Braille => (rows * 4, cols * 2)
+        }
+    }
+}
+
+/// Draws the viewport as lines of characters at the given zoom, the viewport should be sized with
+/// `Zoom::cells_for` so every character is filled.
+pub fn render_zoomed<C: Coordinate>(
+    generation: &HashSet<Cell<C>>,
+    viewport: &Viewport<C>,
+    zoom: Zoom
+) -> Vec<String> {
+    draw_zoomed(viewport, zoom, |cell| generation.contains(&cell) as u8)
+}
+
+/// Like `render_zoomed` with the dying states of Generations rules drawn in fading shades. Half
+/// blocks and braille have no room for that so only live cells show up at those zooms.
+pub fn render_zoomed_states<C: Coordinate>(
+    states: &HashMap<Cell<C>, u8>,
+    viewport: &Viewport<C>,
+    zoom: Zoom
+) -> Vec<String> {
+    draw_zoomed(viewport, zoom, |cell| states.get(&cell).copied().unwrap_or(0))
+}
+
+fn draw_zoomed<C: Coordinate>(viewport: &Viewport<C>, zoom: Zoom, state: impl Fn(Cell<C>) -> u8) -> Vec<String> {
+    let state = |row: usize, col: usize| {
+        if row < viewport.height && col < viewport.width {
+            viewport.cell_at(row, col).map_or(0, &state)
+        } else {
+            0
+        }
+    };
+    let alive = |row: usize, col: usize| state(row, col) == 1;
+    let shade = |row: usize, col: usize| match state(row, col) {
+        0 => ' ',
+        1 => '█',
+        2 => '▓',
+        3 => '▒',
+        _ => '░'
+    };
+
+    let (rows_per_char, cols_per_char) = match zoom {
+        Zoom::Wide | Zoom::Normal => (1, 1),
+        Zoom::HalfBlock => (2, 1),
+        Zoom::Braille => (4, 2)
+    };
+
+    let mut lines = vec![];
+    for row in (0..viewport.height).step_by(rows_per_char) {
+        let mut line = String::new();
+        for col in (0..viewport.width).step_by(cols_per_char) {
+            match zoom {
+                Zoom::Wide => {
+                    line.push(shade(row, col));
+                    line.push(shade(row, col));
+                }
+                Zoom::Normal => line.push(shade(row, col)),
+                Zoom::HalfBlock => line.push(match (alive(row, col), alive(row + 1, col)) {
+                    (false, false) => ' ',
+                    (true, false) => '▀',
+                    (false, true) => '▄',
+                    (true, true) => '█'
+                }),
+                Zoom::Braille => line.push(braille(|dot_row, dot_col| alive(row + dot_row, col + dot_col)))
+            }
+        }
+        lines.push(line);
+    }
+
+    lines
+}
+
+/// Braille character for a 4 row by 2 column block, dots are numbered down the left column then
+/// down the right with the bottom row last.
+fn braille(alive: impl Fn(usize, usize) -> bool) -> char {
+    const DOTS: [(usize, usize, u32); 8] = [
+        (0, 0, 0x01), (1, 0, 0x02), (2, 0, 0x04), (0, 1, 0x08),
+        (1, 1, 0x10), (2, 1, 0x20), (3, 0, 0x40), (3, 1, 0x80)
+    ];
+
+    let bits = DOTS
+        .iter()
+        .filter(|&&(row, col, _)| alive(row, col))
+        .fold(0, |bits, &(_, _, bit)| bits | bit);
+
+    std::char::from_u32(0x2800 + bits).unwrap_or(' ')
+}
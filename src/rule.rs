@@ -0,0 +1,697 @@
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::engine;
+use crate::ltl::LtlRule;
+use crate::table::Table;
+use crate::NEIGHBOR_OFFSETS;
+
+/// Birth/survival rule for the Life-like family, outer-totalistic or isotropic non-totalistic.
+///
+/// Rules are written with the number of live neighbors for birth and survival, `B3/S23`. Hensel
+/// notation adds letters after a number to pick out only some arrangements of that many
+/// neighbors, `B2a` is born on two touching neighbors with one in a corner and `B2-a` on any other
+/// pair. Either way each set is stored as a table over every arrangement of the 8 neighbors.
+///
+/// A `V` or `H` at the end of the rulestring switches to the von Neumann or hexagonal
+/// neighborhood, which only allow counts up to 4 and 6 and no letters.
+///
+/// Generations rules such as `B2/S/C3` have more than 2 states. A live cell that doesn't survive
+/// goes through states 2 up to `states - 1` one generation at a time before it is dead, those
+/// dying cells don't count as neighbors and can't be born into.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Rule {
+    birth: Arrangements,
+    survival: Arrangements,
+    states: u8,
+    neighborhood: Neighborhood
+}
+
+impl Rule {
+    /// Standard Conway's Game of Life, `B3/S23`.
+    pub fn conway() -> Rule {
+        Rule {
+            birth: Arrangements::with_counts(&[3]),
+            survival: Arrangements::with_counts(&[2, 3]),
+            states: 2,
+            neighborhood: Neighborhood::Moore
+        }
+    }
+
+    /// True if a dead cell with `alive` live neighbors comes to life. Only some arrangements of
+    /// them might for a non-totalistic rule, this is true if any does.
+    pub fn is_born(&self, alive: u8) -> bool {
+        self.birth.any_with_count(alive)
+    }
+
+    /// True if a live cell with `alive` live neighbors stays alive, for at least one arrangement
+    /// of them when the rule is non-totalistic.
+    pub fn survives(&self, alive: u8) -> bool {
+        self.survival.any_with_count(alive)
+    }
+
+    /// True if a dead cell comes to life when the neighbors set in `neighbors` are alive, bit 0
+    /// being the top left neighbor through bit 7 the bottom right in reading order. Bits for cells
+    /// outside the rule's neighborhood are ignored.
+    pub fn is_born_with(&self, neighbors: u8) -> bool {
+        self.birth.contains(neighbors & self.neighborhood.mask())
+    }
+
+    /// True if a live cell stays alive when the neighbors set in `neighbors` are alive, bits as
+    /// for `is_born_with`.
+    pub fn survives_with(&self, neighbors: u8) -> bool {
+        self.survival.contains(neighbors & self.neighborhood.mask())
+    }
+
+    pub fn neighborhood(&self) -> Neighborhood {
+        self.neighborhood
+    }
+
+    /// True unless birth or survival depends on where the live neighbors are rather than just
+    /// how many there are.
+    pub fn is_totalistic(&self) -> bool {
+        self.birth.is_totalistic() && self.survival.is_totalistic()
+    }
+
+    /// Number of states including dead and alive, 2 for anything that isn't a Generations rule.
+    pub fn states(&self) -> u8 {
+        self.states
+    }
+
+    /// State a cell in `state` moves to when it doesn't stay alive, 0 once it has run out of
+    /// dying states.
+    pub fn decay(&self, state: u8) -> u8 {
+        engine::decay(self.states, state)
+    }
+
+    /// The legacy survival/birth form used by older file formats, `23/3` for Conway's Life and
+    /// `/2/3` for Generations rules.
+    pub fn to_legacy_string(&self) -> String {
+        if self.states > 2 {
+            format!("{}/{}/{}{}", self.survival, self.birth, self.states, self.neighborhood)
+        } else {
+            format!("{}/{}{}", self.survival, self.birth, self.neighborhood)
+        }
+    }
+}
+
+impl Default for Rule {
+    fn default() -> Rule {
+        Rule::conway()
+    }
+}
+
+/// Which of the 8 surrounding cells count as neighbors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Neighborhood {
+    /// All 8 of them.
+    #[default]
+    Moore,
+    /// The 4 sharing an edge with the cell.
+    VonNeumann,
+    /// A hexagonal grid drawn with each row half a cell to the left of the one above, so the 6
+    /// cells other than the top right and bottom left ones.
+    Hexagonal
+}
+
+impl Neighborhood {
+    /// The neighbors as bits the way `Rule::is_born_with` numbers them.
+    pub fn mask(&self) -> u8 {
+        match self {
+            Neighborhood::Moore => 0b1111_1111,
+            Neighborhood::VonNeumann => 0b0101_1010,
+            Neighborhood::Hexagonal => 0b1101_1011
+        }
+    }
+
+    fn size(&self) -> u8 {
+        self.mask().count_ones() as u8
+    }
+}
+
+/// The rulestring suffix, nothing for Moore.
+impl fmt::Display for Neighborhood {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Neighborhood::Moore => Ok(()),
+            Neighborhood::VonNeumann => write!(f, "V"),
+            Neighborhood::Hexagonal => write!(f, "H")
+        }
+    }
+}
+
+/// Hensel letters for each number of live neighbors, in the order Golly writes them.
+const LETTERS: [&str; 9] = ["", "ce", "ceaikn", "ceaiknjqry", "ceaiknjqrytwz", "ceaiknjqry", "ceaikn", "ce", ""];
+
+/// One arrangement of live neighbors for every letter of 1 to 4 neighbors, in the order of
+/// `LETTERS` and with the bits of `Rule::is_born_with`. The letters of 5 to 7 neighbors are the
+/// dead neighbors of the same letter for 3 to 1.
+const ARRANGEMENTS: [&[u8]; 5] = [
+    &[],
+    &[0b0000_0001, 0b0000_0010],
+    &[0b0000_0101, 0b0000_1010, 0b0000_0011, 0b0001_1000, 0b0001_0001, 0b0010_0100],
+    &[
+        0b0010_0101, 0b0001_1010, 0b0000_1011, 0b0000_0111, 0b0011_0010,
+        0b0000_1101, 0b0000_1110, 0b0010_0110, 0b0001_1001, 0b0011_0001
+    ],
+    &[
+        0b1010_0101, 0b0101_1010, 0b0000_1111, 0b0001_1101, 0b0011_0011, 0b0010_0111, 0b0011_1010,
+        0b0011_0110, 0b0001_1011, 0b0011_0101, 0b0011_1001, 0b0010_1110, 0b0011_1100
+    ]
+];
+
+/// A set of arrangements of live neighbors, bit n of the table set when arrangement n is in it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+struct Arrangements([u64; 4]);
+
+impl Arrangements {
+    fn with_counts(counts: &[u8]) -> Arrangements {
+        let mut arrangements = Arrangements::default();
+        for &count in counts {
+            arrangements.insert_count(count);
+        }
+        arrangements
+    }
+
+    fn contains(&self, neighbors: u8) -> bool {
+        self.0[neighbors as usize / 64] & (1 << (neighbors % 64)) != 0
+    }
+
+    fn insert(&mut self, neighbors: u8) {
+        self.0[neighbors as usize / 64] |= 1 << (neighbors % 64);
+    }
+
+    fn insert_count(&mut self, count: u8) {
+        for neighbors in (0..=255u8).filter(|neighbors| neighbors.count_ones() == count as u32) {
+            self.insert(neighbors);
+        }
+    }
+
+    fn any_with_count(&self, count: u8) -> bool {
+        (0..=255u8).any(|neighbors| neighbors.count_ones() == count as u32 && self.contains(neighbors))
+    }
+
+    fn all_with_count(&self, count: u8) -> bool {
+        (0..=255u8).all(|neighbors| neighbors.count_ones() != count as u32 || self.contains(neighbors))
+    }
+
+    fn is_totalistic(&self) -> bool {
+        (0..=8).all(|count| self.all_with_count(count) || !self.any_with_count(count))
+    }
+}
+
+/// Every rotation and reflection of an arrangement of live neighbors.
+fn symmetries(neighbors: u8) -> Vec<u8> {
+    (0..8)
+        .map(|symmetry| {
+            let mut image = 0;
+            for (index, &(row, col)) in NEIGHBOR_OFFSETS.iter().enumerate() {
+                if neighbors & (1 << index) == 0 {
+                    continue;
+                }
+
+                let (mut row, mut col) = (row, col);
+                for _ in 0..symmetry % 4 {
+                    let turned = (col, -row);
+                    row = turned.0;
+                    col = turned.1;
+                }
+                if symmetry >= 4 {
+                    col = -col;
+                }
+                image |= 1 << NEIGHBOR_OFFSETS.iter().position(|&offset| offset == (row, col)).unwrap_or(index);
+            }
+            image
+        })
+        .collect()
+}
+
+/// One arrangement for the letter at `index` in `LETTERS[count]`.
+fn arrangement(count: u8, index: usize) -> u8 {
+    if count <= 4 {
+        ARRANGEMENTS[count as usize][index]
+    } else {
+        !ARRANGEMENTS[8 - count as usize][index]
+    }
+}
+
+/// Parses a section such as `23` or `2-a3ce` into the arrangements it allows. Hensel letters
+/// only exist for the Moore neighborhood.
+fn parse_arrangements(section: &str, neighborhood: Neighborhood) -> Result<Arrangements, RuleParseError> {
+    let mut arrangements = Arrangements::default();
+    let mut chars = section.chars().peekable();
+    while let Some(ch) = chars.next() {
+        let count = match ch.to_digit(10) {
+            Some(count) if count <= neighborhood.size() as u32 => count as u8,
+            _ => return Err(RuleParseError::InvalidNeighborCount(ch))
+        };
+
+        let negated = chars.next_if_eq(&'-').is_some();
+        let mut letters = vec![];
+        while let Some(letter) = chars.next_if(|letter| letter.is_ascii_lowercase()) {
+            match LETTERS[count as usize].find(letter) {
+                Some(index) if neighborhood == Neighborhood::Moore => letters.push(index),
+                _ => return Err(RuleParseError::InvalidLetter(count, letter))
+            }
+        }
+
+        if letters.is_empty() {
+            if negated {
+                return Err(RuleParseError::InvalidNeighborCount('-'));
+            }
+            arrangements.insert_count(count);
+            continue;
+        }
+
+        // A negated list is every other letter for the count
+        for index in 0..LETTERS[count as usize].len() {
+            if letters.contains(&index) != negated {
+                for neighbors in symmetries(arrangement(count, index)) {
+                    arrangements.insert(neighbors);
+                }
+            }
+        }
+    }
+
+    Ok(arrangements)
+}
+
+fn parse_states(digits: &str) -> Result<u8, RuleParseError> {
+    match digits.parse() {
+        Ok(states) if states >= 2 => Ok(states),
+        _ => Err(RuleParseError::InvalidStates(digits.to_string()))
+    }
+}
+
+/// Counts in increasing order. Counts with only some letters get them listed, or the ones left
+/// out after a `-` when that is shorter.
+impl fmt::Display for Arrangements {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for count in 0..=8u8 {
+            if !self.any_with_count(count) {
+                continue;
+            }
+
+            write!(f, "{}", count)?;
+            if self.all_with_count(count) {
+                continue;
+            }
+
+            let (included, excluded): (String, String) = LETTERS[count as usize]
+                .chars()
+                .enumerate()
+                .map(|(index, letter)| (letter, self.contains(arrangement(count, index))))
+                .fold((String::new(), String::new()), |(mut included, mut excluded), (letter, contained)| {
+                    if contained {
+                        included.push(letter);
+                    } else {
+                        excluded.push(letter);
+                    }
+                    (included, excluded)
+                });
+
+            if excluded.len() < included.len() {
+                write!(f, "-{}", excluded)?;
+            } else {
+                write!(f, "{}", included)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Parses `B3/S23` style rulestrings (either order, case insensitive apart from Hensel letters) as
+/// well as the legacy `23/3` form where survival comes before birth. Generations rules add a third
+/// section with the number of states, `B2/S/C3` or `/2/3`. Either form can end in `V` or `H` for
+/// another neighborhood.
+impl FromStr for Rule {
+    type Err = RuleParseError;
+
+    fn from_str(s: &str) -> Result<Rule, RuleParseError> {
+        let s = s.trim();
+        if s.is_empty() {
+            return Err(RuleParseError::Empty);
+        }
+
+        let (s, neighborhood) = match s.chars().last() {
+            Some('V') | Some('v') => (&s[..s.len() - 1], Neighborhood::VonNeumann),
+            Some('H') | Some('h') => (&s[..s.len() - 1], Neighborhood::Hexagonal),
+            _ => (s, Neighborhood::Moore)
+        };
+
+        let sections: Vec<&str> = s.split('/').collect();
+        if sections.len() != 2 && sections.len() != 3 {
+            return Err(RuleParseError::WrongSectionCount(sections.len()));
+        }
+
+        let mut birth = None;
+        let mut survival = None;
+        let mut states = None;
+        let mut prefixed = 0;
+        for section in &sections {
+            let mut chars = section.chars();
+            match chars.next() {
+                Some('B') | Some('b') => {
+                    if birth.is_some() {
+                        return Err(RuleParseError::DuplicateSection('B'));
+                    }
+                    birth = Some(parse_arrangements(chars.as_str(), neighborhood)?);
+                    prefixed += 1;
+                }
+                Some('S') | Some('s') => {
+                    if survival.is_some() {
+                        return Err(RuleParseError::DuplicateSection('S'));
+                    }
+                    survival = Some(parse_arrangements(chars.as_str(), neighborhood)?);
+                    prefixed += 1;
+                }
+                Some('C') | Some('c') => {
+                    if states.is_some() {
+                        return Err(RuleParseError::DuplicateSection('C'));
+                    }
+                    states = Some(parse_states(chars.as_str())?);
+                    prefixed += 1;
+                }
+                _ => {}
+            }
+        }
+
+        let (birth, survival, states) = match prefixed {
+            // Legacy notation lists survival first: `23/3`, or `/2/3` for Generations
+            0 => (
+                parse_arrangements(sections[1], neighborhood)?,
+                parse_arrangements(sections[0], neighborhood)?,
+                match sections.get(2) {
+                    Some(states) => parse_states(states)?,
+                    None => 2
+                }
+            ),
+            prefixed if prefixed == sections.len() && birth.is_some() && survival.is_some() => {
+                (birth.unwrap_or_default(), survival.unwrap_or_default(), states.unwrap_or(2))
+            }
+            _ => return Err(RuleParseError::MixedNotation)
+        };
+
+        let rule = Rule { birth, survival, states, neighborhood };
+        if rule.is_born(0) {
+            return Err(RuleParseError::BirthOnZero);
+        }
+
+        Ok(rule)
+    }
+}
+
+/// Always written in the canonical `B.../S...` form.
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "B{}/S{}", self.birth, self.survival)?;
+        if self.states > 2 {
+            write!(f, "/C{}", self.states)?;
+        }
+
+        write!(f, "{}", self.neighborhood)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RuleParseError {
+    Empty,
+    /// Rulestrings have two `/` separated sections, or three for Generations rules, holds how
+    /// many were found.
+    WrongSectionCount(usize),
+    /// The same `B` or `S` section, or Larger than Life parameter, appeared twice.
+    DuplicateSection(char),
+    /// Some sections used a `B`/`S`/`C` prefix and others did not, or a prefixed rule is
+    /// missing its `B` or `S` section.
+    MixedNotation,
+    /// Anything other than the counts the neighborhood allows and their Hensel letters in a
+    /// section, or a `-` with no letters after it.
+    InvalidNeighborCount(char),
+    /// A Hensel letter that doesn't exist for that many neighbors.
+    InvalidLetter(u8, char),
+    /// The number of states of a Generations rule isn't a number from 2 to 255.
+    InvalidStates(String),
+    /// `B0` rules would turn the whole infinite background on, which the sparse engine can't
+    /// represent.
+    BirthOnZero,
+    /// A Larger than Life rule without its `R`, `S` or `B` parameter.
+    MissingParameter(char),
+    /// A Larger than Life parameter that is unknown, malformed or out of range.
+    InvalidParameter(String)
+}
+
+impl fmt::Display for RuleParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RuleParseError::Empty => write!(f, "rulestring is empty"),
+            RuleParseError::WrongSectionCount(count) => {
+                write!(f, "expected 2 or 3 sections separated by '/', found {}", count)
+            }
+            RuleParseError::DuplicateSection(section) => {
+                write!(f, "section '{}' appears more than once", section)
+            }
+            RuleParseError::MixedNotation => {
+                write!(f, "either every section or none must have a B/S/C prefix")
+            }
+            RuleParseError::InvalidNeighborCount(ch) => {
+                write!(f, "'{}' is not a neighbor count the neighborhood allows", ch)
+            }
+            RuleParseError::InvalidLetter(count, letter) => {
+                write!(f, "'{}' is not a Hensel letter for {} neighbors", letter, count)
+            }
+            RuleParseError::InvalidStates(states) => {
+                write!(f, "'{}' is not a number of states between 2 and 255", states)
+            }
+            RuleParseError::BirthOnZero => write!(f, "B0 rules are not supported"),
+            RuleParseError::MissingParameter(parameter) => {
+                write!(f, "Larger than Life rules need a '{}' parameter", parameter)
+            }
+            RuleParseError::InvalidParameter(parameter) => {
+                write!(f, "'{}' is not a valid Larger than Life parameter", parameter)
+            }
+        }
+    }
+}
+
+impl Error for RuleParseError {}
+
+/// Any rule the engines can run, what pattern files and `--rule` accept.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum AnyRule {
+    /// Life-like and Generations rules such as `B3/S23`.
+    Life(Rule),
+    /// Larger than Life rules such as `R5,C0,M1,S34..58,B34..45,NM`.
+    LargerThanLife(LtlRule),
+    /// `WireWorld`, see `automaton::Wireworld`.
+    Wireworld,
+    /// A rule table read from a `.rule` file.
+    Table(Table),
+    /// Any other rule name, such as a pattern file asking for a rule table by name. It can't be
+    /// run until the table itself is loaded.
+    Named(String)
+}
+
+impl Default for AnyRule {
+    fn default() -> AnyRule {
+        AnyRule::Life(Rule::conway())
+    }
+}
+
+impl From<Rule> for AnyRule {
+    fn from(rule: Rule) -> AnyRule {
+        AnyRule::Life(rule)
+    }
+}
+
+impl From<LtlRule> for AnyRule {
+    fn from(rule: LtlRule) -> AnyRule {
+        AnyRule::LargerThanLife(rule)
+    }
+}
+
+impl From<Table> for AnyRule {
+    fn from(table: Table) -> AnyRule {
+        AnyRule::Table(table)
+    }
+}
+
+/// Larger than Life rules are the ones starting with `R` and a range, no Life-like rulestring can.
+/// Names such as `WireWorld` and `BriansBrain` are matched ignoring case, any other name made of
+/// letters, digits, `-` and `_` that isn't a Life-like rule is kept as `AnyRule::Named`.
+impl FromStr for AnyRule {
+    type Err = RuleParseError;
+
+    fn from_str(s: &str) -> Result<AnyRule, RuleParseError> {
+        let name = s.trim();
+        match name.to_ascii_lowercase().as_str() {
+            "wireworld" => return Ok(AnyRule::Wireworld),
+            "briansbrain" => return Ok(AnyRule::Life("B2/S/C3".parse()?)),
+            _ => {}
+        }
+
+        let mut chars = name.chars();
+        match (chars.next(), chars.next()) {
+            (Some('R'), Some(digit)) | (Some('r'), Some(digit)) if digit.is_ascii_digit() => {
+                Ok(AnyRule::LargerThanLife(s.parse()?))
+            }
+            _ => match s.parse() {
+                Ok(rule) => Ok(AnyRule::Life(rule)),
+                Err(err) => {
+                    let is_name = name.starts_with(|ch: char| ch.is_ascii_alphabetic())
+                        && name.chars().all(|ch| ch.is_ascii_alphanumeric() || ch == '-' || ch == '_');
+                    if is_name {
+                        Ok(AnyRule::Named(name.to_string()))
+                    } else {
+                        Err(err)
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl fmt::Display for AnyRule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AnyRule::Life(rule) => rule.fmt(f),
+            AnyRule::LargerThanLife(rule) => rule.fmt(f),
+            AnyRule::Wireworld => write!(f, "WireWorld"),
+            AnyRule::Table(table) => table.fmt(f),
+            AnyRule::Named(name) => write!(f, "{}", name)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Every Hensel letter for 1 to 4 neighbors as Golly's documentation draws it, `o` for a live
+    /// neighbor.
+    const DRAWINGS: [(&str, [&str; 3]); 31] = [
+        ("1c", ["o..", ".x.", "..."]),
+        ("1e", [".o.", ".x.", "..."]),
+        ("2c", ["o.o", ".x.", "..."]),
+        ("2e", [".o.", "ox.", "..."]),
+        ("2k", ["o..", ".xo", "..."]),
+        ("2a", ["oo.", ".x.", "..."]),
+        ("2i", ["...", "oxo", "..."]),
+        ("2n", ["..o", ".x.", "o.."]),
+        ("3c", ["o.o", ".x.", "o.."]),
+        ("3e", [".o.", "oxo", "..."]),
+        ("3k", [".o.", ".xo", "o.."]),
+        ("3a", ["oo.", "ox.", "..."]),
+        ("3i", ["ooo", ".x.", "..."]),
+        ("3n", ["o.o", "ox.", "..."]),
+        ("3y", ["o..", ".xo", "o.."]),
+        ("3q", [".oo", ".x.", "o.."]),
+        ("3j", [".oo", "ox.", "..."]),
+        ("3r", ["o..", "oxo", "..."]),
+        ("4c", ["o.o", ".x.", "o.o"]),
+        ("4e", [".o.", "oxo", ".o."]),
+        ("4k", ["oo.", ".xo", "o.."]),
+        ("4a", ["ooo", "ox.", "..."]),
+        ("4i", ["o.o", "oxo", "..."]),
+        ("4n", ["o.o", "ox.", "o.."]),
+        ("4y", ["o.o", ".xo", "o.."]),
+        ("4q", [".oo", ".xo", "o.."]),
+        ("4j", [".o.", "oxo", "o.."]),
+        ("4r", ["oo.", "oxo", "..."]),
+        ("4t", ["o..", "oxo", "o.."]),
+        ("4w", [".oo", "ox.", "o.."]),
+        ("4z", ["..o", "oxo", "o.."])
+    ];
+
+    /// The neighbors drawn in a 3x3 drawing as bits.
+    fn neighbors(drawing: [&str; 3]) -> u8 {
+        NEIGHBOR_OFFSETS
+            .iter()
+            .enumerate()
+            .filter(|&(_, &(row, col))| drawing[(row + 1) as usize].as_bytes()[(col + 1) as usize] == b'o')
+            .fold(0, |neighbors, (index, _)| neighbors | 1 << index)
+    }
+
+    #[test]
+    fn round_trips() {
+        let rules = [
+            ("B3/S23", "B3/S23", "23/3"),
+            ("23/3", "B3/S23", "23/3"),
+            ("s23/b36", "B36/S23", "23/36"),
+            ("B2/S/C3", "B2/S/C3", "/2/3"),
+            ("/2/3", "B2/S/C3", "/2/3"),
+            ("B2-a/S12", "B2-a/S12", "12/2-a"),
+            ("B2ce3-ik/S1e2-n", "B2ce3-ik/S1e2-n", "1e2-n/2ce3-ik"),
+            ("B2/S34H", "B2/S34H", "34/2H"),
+            ("B3/S23V", "B3/S23V", "23/3V")
+        ];
+        for &(rulestring, canonical, legacy) in &rules {
+            let rule: Rule = rulestring.parse().unwrap();
+            assert_eq!(rule.to_string(), canonical, "{}", rulestring);
+            assert_eq!(rule.to_legacy_string(), legacy, "{}", rulestring);
+            assert_eq!(canonical.parse::<Rule>(), Ok(rule), "{}", rulestring);
+            assert_eq!(legacy.parse::<Rule>(), Ok(rule), "{}", rulestring);
+        }
+    }
+
+    #[test]
+    fn errors() {
+        let rules = [
+            ("", RuleParseError::Empty),
+            ("B3", RuleParseError::WrongSectionCount(1)),
+            ("B3/S2/S3/B3", RuleParseError::WrongSectionCount(4)),
+            ("B3/B3", RuleParseError::DuplicateSection('B')),
+            ("B3/23", RuleParseError::MixedNotation),
+            ("B3/C3", RuleParseError::MixedNotation),
+            ("B9/S", RuleParseError::InvalidNeighborCount('9')),
+            ("B5/SV", RuleParseError::InvalidNeighborCount('5')),
+            ("B7/SH", RuleParseError::InvalidNeighborCount('7')),
+            ("B2-/S", RuleParseError::InvalidNeighborCount('-')),
+            ("B3x/S", RuleParseError::InvalidLetter(3, 'x')),
+            ("B2a/SV", RuleParseError::InvalidLetter(2, 'a')),
+            ("B2/S/C1", RuleParseError::InvalidStates("1".to_string())),
+            ("B2/S/C", RuleParseError::InvalidStates(String::new())),
+            ("B0/S", RuleParseError::BirthOnZero)
+        ];
+        for (rulestring, err) in rules {
+            assert_eq!(rulestring.parse::<Rule>(), Err(err), "{}", rulestring);
+        }
+    }
+
+    #[test]
+    fn letters_match_golly() {
+        for &(name, drawing) in &DRAWINGS {
+            let count = name.as_bytes()[0] - b'0';
+            let index = LETTERS[count as usize].find(name.chars().nth(1).unwrap()).unwrap();
+            assert!(symmetries(arrangement(count, index)).contains(&neighbors(drawing)), "{}", name);
+        }
+    }
+
+    /// Each letter picks out its own arrangement and no other, including the letters of 5 to 7
+    /// neighbors which are the dead neighbors of the drawings for 3 to 1.
+    #[test]
+    fn letters_pick_out_one_arrangement() {
+        let drawn = |count: u8| -> Vec<(char, u8)> {
+            DRAWINGS
+                .iter()
+                .filter(|(name, _)| name.as_bytes()[0] - b'0' == count.min(8 - count))
+                .map(|&(name, drawing)| {
+                    let neighbors = neighbors(drawing);
+                    (name.chars().nth(1).unwrap(), if count > 4 { !neighbors } else { neighbors })
+                })
+                .collect()
+        };
+
+        for count in 1..=7 {
+            let arrangements = drawn(count);
+            assert_eq!(arrangements.len(), LETTERS[count as usize].len());
+            for &(letter, _) in &arrangements {
+                let rule: Rule = format!("B{}{}/S", count, letter).parse().unwrap();
+                for &(other, neighbors) in &arrangements {
+                    assert_eq!(rule.is_born_with(neighbors), letter == other, "B{}{} with {}", count, letter, other);
+                }
+            }
+        }
+    }
+}
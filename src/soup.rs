@@ -0,0 +1,290 @@
+//! Random soups that can be recreated from their seed string.
+//!
+//! The seed is hashed with 64 bit FNV-1a and fed to a splitmix64 generator, both simple enough to
+//! give the same soup on every platform and every version of this crate. They are not the soups
+//! apgsearch would make from the same seed, which hashes with SHA-256.
+
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::coordinate::Coordinate;
+use crate::Cell;
+
+/// Symmetry forced onto a soup, named as in apgsearch.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Symmetry {
+    /// No symmetry.
+    #[default]
+    C1,
+    /// Unchanged by a half turn.
+    C2,
+    /// Unchanged by a quarter turn, needs a square soup.
+    C4,
+    /// Mirrored left to right and top to bottom.
+    D4,
+    /// Unchanged by any rotation or reflection of the square, needs a square soup.
+    D8
+}
+
+impl Symmetry {
+    fn needs_square(&self) -> bool {
+        matches!(self, Symmetry::C4 | Symmetry::D8)
+    }
+
+    /// Every cell the symmetry maps `(row, col)` to inside a `height` by `width` soup, including
+    /// the cell itself.
+    fn images(&self, (row, col): (usize, usize), height: usize, width: usize) -> Vec<(usize, usize)> {
+        let (bottom, right) = (height - 1, width - 1);
+        let half_turn = (bottom - row, right - col);
+
+        match self {
+            Symmetry::C1 => vec![(row, col)],
+            Symmetry::C2 => vec![(row, col), half_turn],
+            Symmetry::C4 => vec![(row, col), (col, right - row), half_turn, (right - col, row)],
+            Symmetry::D4 => vec![(row, col), (row, right - col), (bottom - row, col), half_turn],
+            Symmetry::D8 => vec![
+                (row, col), (col, right - row), half_turn, (right - col, row),
+                (row, right - col), (bottom - row, col), (col, row), (right - col, bottom - row)
+            ]
+        }
+    }
+}
+
+impl fmt::Display for Symmetry {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(self, f)
+    }
+}
+
+impl FromStr for Symmetry {
+    type Err = SoupError;
+
+    fn from_str(s: &str) -> Result<Symmetry, SoupError> {
+        match s.to_ascii_uppercase().as_str() {
+            "C1" => Ok(Symmetry::C1),
+            "C2" => Ok(Symmetry::C2),
+            "C4" => Ok(Symmetry::C4),
+            "D4" => Ok(Symmetry::D4),
+            "D8" => Ok(Symmetry::D8),
+            _ => Err(SoupError::UnknownSymmetry(s.to_string()))
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SoupError {
+    /// Density has to be between 0 and 1.
+    InvalidDensity(f64),
+    /// C4 and D8 only map a square onto itself.
+    NotSquare(Symmetry),
+    UnknownSymmetry(String)
+}
+
+impl fmt::Display for SoupError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SoupError::InvalidDensity(density) => write!(f, "density {} is not between 0 and 1", density),
+            SoupError::NotSquare(symmetry) => write!(f, "{} symmetry needs a square soup", symmetry),
+            SoupError::UnknownSymmetry(name) => {
+                write!(f, "unknown symmetry '{}', expected C1, C2, C4, D4 or D8", name)
+            }
+        }
+    }
+}
+
+impl Error for SoupError {}
+
+/// Recipe for random soups, the same seed string always gives the same cells.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Soup {
+    width: usize,
+    height: usize,
+    density: f64,
+    symmetry: Symmetry
+}
+
+impl Soup {
+    /// Soups of `width` by `height` cells where each cell is alive with probability `density`.
+    pub fn new(width: usize, height: usize, density: f64, symmetry: Symmetry) -> Result<Soup, SoupError> {
+        if !(0.0..=1.0).contains(&density) {
+            return Err(SoupError::InvalidDensity(density));
+        }
+        if symmetry.needs_square() && width != height {
+            return Err(SoupError::NotSquare(symmetry));
+        }
+
+        Ok(Soup { width, height, density, symmetry })
+    }
+
+    /// The soup for `seed` with its top left cell at the origin, ready for
+    /// `GOLGenerationIterator::new`. Cells past the range of `C` are left out.
+    pub fn generate<C: Coordinate>(&self, seed: &str) -> Vec<Cell<C>> {
+        let mut rng = SplitMix64::new(fnv1a(seed.as_bytes()));
+        let mut alive = vec![false; self.width * self.height];
+        let mut cells = vec![];
+
+        // Only the first cell of each set of symmetric cells in reading order gets a random draw,
+        // the rest copy it
+        for row in 0..self.height {
+            for col in 0..self.width {
+                let first = self
+                    .symmetry
+                    .images((row, col), self.height, self.width)
+                    .into_iter()
+                    .min()
+                    .unwrap_or((row, col));
+
+                alive[row * self.width + col] = if first == (row, col) {
+                    rng.next_f64() < self.density
+                } else {
+                    alive[first.0 * self.width + first.1]
+                };
+
+                if alive[row * self.width + col] {
+                    if let (Some(row), Some(col)) = (C::from_i128(row as i128), C::from_i128(col as i128)) {
+                        cells.push((row, col));
+                    }
+                }
+            }
+        }
+
+        cells
+    }
+}
+
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &byte| {
+        (hash ^ byte as u64).wrapping_mul(0x0000_0100_0000_01b3)
+    })
+}
+
+struct SplitMix64 {
+    state: u64
+}
+
+impl SplitMix64 {
+    fn new(seed: u64) -> SplitMix64 {
+        SplitMix64 { state: seed }
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform in `[0, 1)` from the top 53 bits.
+    fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    fn drawn(cells: &[Cell], height: i64, width: i64) -> Vec<String> {
+        (0..height).map(|row| (0..width).map(|col| if cells.contains(&(row, col)) { 'o' } else { '.' }).collect()).collect()
+    }
+
+    /// Every cell moved by `map`, which gets the cell and the soup's bottom row and right column.
+    fn moved(cells: &HashSet<Cell>, map: fn(Cell, i64, i64) -> Cell, height: i64, width: i64) -> HashSet<Cell> {
+        cells.iter().map(|&cell| map(cell, height - 1, width - 1)).collect()
+    }
+
+    fn assert_unchanged_by(symmetry: Symmetry, height: usize, width: usize, maps: &[fn(Cell, i64, i64) -> Cell]) {
+        let soup = Soup::new(width, height, 0.5, symmetry).unwrap();
+        for seed in &["k_test0", "k_test1", "k_test2"] {
+            let cells: HashSet<Cell> = soup.generate(seed).into_iter().collect();
+            assert!(!cells.is_empty());
+            for map in maps {
+                assert_eq!(moved(&cells, *map, height as i64, width as i64), cells, "{} soup {}", symmetry, seed);
+            }
+        }
+    }
+
+    fn half_turn((row, col): Cell, bottom: i64, right: i64) -> Cell { (bottom - row, right - col) }
+    fn quarter_turn((row, col): Cell, _: i64, right: i64) -> Cell { (col, right - row) }
+    fn mirror_left_right((row, col): Cell, _: i64, right: i64) -> Cell { (row, right - col) }
+    fn mirror_top_bottom((row, col): Cell, bottom: i64, _: i64) -> Cell { (bottom - row, col) }
+    fn transpose((row, col): Cell, _: i64, _: i64) -> Cell { (col, row) }
+
+    #[test]
+    fn same_seed_same_soup() {
+        let soup = Soup::new(8, 8, 0.5, Symmetry::C1).unwrap();
+        let cells: Vec<Cell> = soup.generate("k_test0");
+
+        assert_eq!(drawn(&cells, 8, 8), vec![
+            ".o....oo",
+            "o...o.oo",
+            "..o..oo.",
+            "..oooooo",
+            "o.....oo",
+            "ooo..o.o",
+            "..ooooo.",
+            ".o.o..o."
+        ]);
+        assert_eq!(soup.generate::<i64>("k_test0"), cells);
+        assert_ne!(soup.generate::<i64>("k_test1"), cells);
+    }
+
+    #[test]
+    fn hash_and_generator_are_the_reference_ones() {
+        assert_eq!(fnv1a(b""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(fnv1a(b"a"), 0xaf63_dc4c_8601_ec8c);
+        assert_eq!(SplitMix64::new(0).next_u64(), 0xe220_a839_7b1d_cdaf);
+    }
+
+    #[test]
+    fn density_scales_the_population() {
+        let empty: Vec<Cell> = Soup::new(16, 16, 0.0, Symmetry::C1).unwrap().generate("k_test0");
+        let full: Vec<Cell> = Soup::new(16, 16, 1.0, Symmetry::C1).unwrap().generate("k_test0");
+
+        assert!(empty.is_empty());
+        assert_eq!(full.len(), 256);
+    }
+
+    #[test]
+    fn c2_soups() {
+        assert_unchanged_by(Symmetry::C2, 16, 16, &[half_turn]);
+        assert_unchanged_by(Symmetry::C2, 9, 12, &[half_turn]);
+    }
+
+    #[test]
+    fn c4_soups() {
+        assert_unchanged_by(Symmetry::C4, 16, 16, &[quarter_turn, half_turn]);
+        assert_unchanged_by(Symmetry::C4, 15, 15, &[quarter_turn, half_turn]);
+    }
+
+    #[test]
+    fn d4_soups() {
+        assert_unchanged_by(Symmetry::D4, 16, 16, &[mirror_left_right, mirror_top_bottom]);
+        assert_unchanged_by(Symmetry::D4, 9, 12, &[mirror_left_right, mirror_top_bottom]);
+    }
+
+    #[test]
+    fn d8_soups() {
+        assert_unchanged_by(Symmetry::D8, 16, 16, &[quarter_turn, mirror_left_right, transpose]);
+        assert_unchanged_by(Symmetry::D8, 15, 15, &[quarter_turn, mirror_left_right, transpose]);
+    }
+
+    #[test]
+    fn c1_soups_are_not_forced_symmetric() {
+        let cells: HashSet<Cell> = Soup::new(16, 16, 0.5, Symmetry::C1).unwrap().generate("k_test0").into_iter().collect();
+
+        assert_ne!(moved(&cells, half_turn, 16, 16), cells);
+        assert_ne!(moved(&cells, mirror_left_right, 16, 16), cells);
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(Soup::new(16, 12, 0.5, Symmetry::C4), Err(SoupError::NotSquare(Symmetry::C4)));
+        assert_eq!(Soup::new(16, 12, 0.5, Symmetry::D8), Err(SoupError::NotSquare(Symmetry::D8)));
+        assert_eq!(Soup::new(16, 16, 1.5, Symmetry::C1), Err(SoupError::InvalidDensity(1.5)));
+        assert_eq!("C3".parse::<Symmetry>(), Err(SoupError::UnknownSymmetry("C3".to_string())));
+    }
+}
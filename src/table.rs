@@ -0,0 +1,599 @@
+//! Rule tables in Golly's `.rule` format, how rules such as Langton's loops are shared.
+//!
+//! A file starts with `@RULE name` and the `@TABLE` section lists the number of states, the
+//! neighborhood, the symmetries and then transitions, each one the cell's state, its neighbors
+//! clockwise from the top and the state it moves to:
+//!
+//! ```text
+//! @RULE Example
+//! @TABLE
+//! n_states:3
+//! neighborhood:vonNeumann
+//! symmetries:rotate4
+//! var a={0,1,2}
+//! 0,1,a,a,0,2
+//! ```
+//!
+//! Variables stand for any of their states, a variable used more than once in a transition has to
+//! be the same state everywhere it is used. The first transition that matches wins and a cell
+//! nothing matches keeps its state. Other sections such as `@COLORS` are skipped.
+
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::automaton::StateRule;
+use crate::rule::Neighborhood;
+
+/// Most inputs a transition can have, the cell and its 8 neighbors.
+const MAX_INPUTS: usize = 9;
+
+/// Which variable each slot of a transition has been bound to so far.
+type Bindings = [Option<u8>; MAX_INPUTS];
+
+/// A rule read from a `.rule` file.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Table {
+    name: String,
+    states: u16,
+    neighborhood: Neighborhood,
+    symmetries: Symmetries,
+    transitions: Vec<Transition>
+}
+
+impl Table {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn neighborhood(&self) -> Neighborhood {
+        self.neighborhood
+    }
+}
+
+impl StateRule for Table {
+    fn states(&self) -> u16 {
+        self.states
+    }
+
+    fn next_state(&self, state: u8, neighbors: [u8; 8]) -> u8 {
+        let ring: Vec<u8> = clockwise(self.neighborhood).iter().map(|&index| neighbors[index]).collect();
+        self.transitions
+            .iter()
+            .find_map(|transition| transition.apply(state, &ring, &self.symmetries))
+            .unwrap_or(state)
+    }
+}
+
+impl fmt::Display for Table {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name)
+    }
+}
+
+/// Positions of the neighbors in `StateRule::next_state` in the order tables list them,
+/// clockwise from the top.
+fn clockwise(neighborhood: Neighborhood) -> &'static [usize] {
+    match neighborhood {
+        Neighborhood::Moore => &[1, 2, 4, 7, 6, 5, 3, 0],
+        Neighborhood::VonNeumann => &[1, 4, 6, 3],
+        Neighborhood::Hexagonal => &[1, 4, 7, 6, 3, 0]
+    }
+}
+
+/// Ways the neighbors can be rearranged and still match a transition.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum Symmetries {
+    /// Each one lists which neighbor is compared against each of the transition's neighbors
+    Orders(Vec<Vec<usize>>),
+    /// Any order at all
+    Permute
+}
+
+impl Symmetries {
+    /// `rotateN` turns the neighbors through N evenly spaced steps and `reflect` adds their
+    /// mirror images, `reflect_horizontal` is the mirror image alone.
+    fn parse(name: &str, neighbors: usize) -> Option<Symmetries> {
+        let (rotations, reflect) = match name {
+            "none" => (1, false),
+            "permute" => return Some(Symmetries::Permute),
+            "reflect_horizontal" => (1, true),
+            name => {
+                let rotations = name.strip_prefix("rotate")?;
+                let (rotations, reflect) = match rotations.strip_suffix("reflect") {
+                    Some(rotations) => (rotations, true),
+                    None => (rotations, false)
+                };
+                (rotations.parse::<usize>().ok().filter(|&rotations| rotations > 1)?, reflect)
+            }
+        };
+        if !neighbors.is_multiple_of(rotations) {
+            return None;
+        }
+
+        let mut orders = vec![];
+        for rotation in 0..rotations {
+            let shift = rotation * neighbors / rotations;
+            orders.push((0..neighbors).map(|index| (index + shift) % neighbors).collect::<Vec<_>>());
+            if reflect {
+                orders.push((0..neighbors).map(|index| (neighbors - index + shift) % neighbors).collect());
+            }
+        }
+
+        Some(Symmetries::Orders(orders))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum Entry {
+    State(u8),
+    /// A variable's states as a bit set, and the binding slot it shares with every other use of
+    /// the same variable in the transition
+    Variable([u64; 4], usize)
+}
+
+impl Entry {
+    fn matches(&self, state: u8, bindings: &mut Bindings) -> bool {
+        match *self {
+            Entry::State(expected) => state == expected,
+            Entry::Variable(states, slot) => match bindings[slot] {
+                Some(bound) => bound == state,
+                None if contains(&states, state) => {
+                    bindings[slot] = Some(state);
+                    true
+                }
+                None => false
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct Transition {
+    state: Entry,
+    /// Clockwise from the top
+    neighbors: Vec<Entry>,
+    /// A state, or a variable from the inputs as its binding slot
+    output: Entry
+}
+
+impl Transition {
+    fn apply(&self, state: u8, ring: &[u8], symmetries: &Symmetries) -> Option<u8> {
+        let mut bindings = [None; MAX_INPUTS];
+        if !self.state.matches(state, &mut bindings) {
+            return None;
+        }
+
+        let bindings = match symmetries {
+            Symmetries::Orders(orders) => orders.iter().find_map(|order| {
+                let mut bindings = bindings;
+                let matched = self
+                    .neighbors
+                    .iter()
+                    .zip(order)
+                    .all(|(entry, &index)| entry.matches(ring[index], &mut bindings));
+                Some(bindings).filter(|_| matched)
+            })?,
+            Symmetries::Permute => permuted(&self.neighbors, ring, 0, bindings)?
+        };
+
+        match self.output {
+            Entry::State(output) => Some(output),
+            Entry::Variable(_, slot) => bindings[slot]
+        }
+    }
+}
+
+/// Matches `entries` against the neighbors not yet `used` in any order, trying each different
+/// state once per entry.
+fn permuted(entries: &[Entry], ring: &[u8], used: u16, bindings: Bindings) -> Option<Bindings> {
+    let (entry, rest) = match entries.split_first() {
+        Some(split) => split,
+        None => return Some(bindings)
+    };
+
+    let unused = |index: &usize| used & (1 << index) == 0;
+    for (index, &state) in ring.iter().enumerate().filter(|(index, _)| unused(index)) {
+        let tried = (0..index).filter(unused).any(|earlier| ring[earlier] == state);
+        let mut bindings = bindings;
+        if !tried && entry.matches(state, &mut bindings) {
+            if let Some(bindings) = permuted(rest, ring, used | (1 << index), bindings) {
+                return Some(bindings);
+            }
+        }
+    }
+
+    None
+}
+
+fn contains(states: &[u64; 4], state: u8) -> bool {
+    states[state as usize / 64] & (1 << (state % 64)) != 0
+}
+
+impl FromStr for Table {
+    type Err = TableError;
+
+    fn from_str(s: &str) -> Result<Table, TableError> {
+        let mut name = None;
+        let mut found_table = false;
+        let mut in_table = false;
+        let mut states = None;
+        let mut neighborhood = Neighborhood::Moore;
+        let mut symmetries = None;
+        let mut variables: Vec<(String, [u64; 4])> = vec![];
+        let mut transitions = vec![];
+
+        for (index, line) in s.lines().enumerate() {
+            let error = |kind| TableError { line: index + 1, kind };
+            let line = line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            if let Some(section) = line.strip_prefix('@') {
+                let mut words = section.split_whitespace();
+                let keyword = words.next();
+                if keyword == Some("RULE") {
+                    name = words.next().map(str::to_string);
+                }
+                in_table = keyword == Some("TABLE");
+                found_table |= in_table;
+                continue;
+            }
+            if !in_table {
+                continue;
+            }
+
+            if let Some((key, value)) = line.split_once(':') {
+                let value = value.trim();
+                match key.trim() {
+                    "n_states" => {
+                        let count = value.parse::<u16>().ok().filter(|count| (2..=256).contains(count));
+                        states = Some(count.ok_or_else(|| error(TableErrorKind::InvalidStates(value.to_string())))?);
+                    }
+                    "neighborhood" => {
+                        neighborhood = match value {
+                            "Moore" => Neighborhood::Moore,
+                            "vonNeumann" => Neighborhood::VonNeumann,
+                            "hexagonal" => Neighborhood::Hexagonal,
+                            _ => return Err(error(TableErrorKind::UnsupportedNeighborhood(value.to_string())))
+                        };
+                    }
+                    "symmetries" => {
+                        let parsed = Symmetries::parse(value, clockwise(neighborhood).len())
+                            .ok_or_else(|| error(TableErrorKind::UnsupportedSymmetries(value.to_string())))?;
+                        symmetries = Some(parsed);
+                    }
+                    _ => return Err(error(TableErrorKind::InvalidLine(line.to_string())))
+                }
+                continue;
+            }
+
+            let states = states.ok_or_else(|| error(TableErrorKind::MissingStates))?;
+            let lookup = |value: &str| -> Result<[u64; 4], TableError> {
+                if let Some(state) = value.parse::<u8>().ok().filter(|&state| (state as u16) < states) {
+                    let mut set = [0; 4];
+                    set[state as usize / 64] |= 1 << (state % 64);
+                    return Ok(set);
+                }
+                variables
+                    .iter()
+                    .rev()
+                    .find(|(name, _)| name == value)
+                    .map(|&(_, set)| set)
+                    .ok_or_else(|| error(TableErrorKind::UnknownValue(value.to_string())))
+            };
+
+            if let Some(variable) = line.strip_prefix("var ") {
+                let (name, values) = variable
+                    .split_once('=')
+                    .ok_or_else(|| error(TableErrorKind::InvalidLine(line.to_string())))?;
+                let values = values
+                    .trim()
+                    .strip_prefix('{')
+                    .and_then(|values| values.strip_suffix('}'))
+                    .ok_or_else(|| error(TableErrorKind::InvalidLine(line.to_string())))?;
+                let mut set = [0u64; 4];
+                for value in values.split(',') {
+                    let other = lookup(value.trim())?;
+                    for (bits, other) in set.iter_mut().zip(other.iter()) {
+                        *bits |= other;
+                    }
+                }
+                variables.push((name.trim().to_string(), set));
+                continue;
+            }
+
+            // Tables with fewer than 11 states can leave out the commas
+            let values: Vec<String> = if line.contains(',') {
+                line.split(',').map(|value| value.trim().to_string()).collect()
+            } else {
+                line.chars().filter(|ch| !ch.is_whitespace()).map(String::from).collect()
+            };
+            let expected = clockwise(neighborhood).len() + 2;
+            if values.len() != expected {
+                return Err(error(TableErrorKind::WrongEntryCount(expected, values.len())));
+            }
+
+            let mut slots: Vec<&str> = vec![];
+            let mut entries = vec![];
+            for value in &values[..expected - 1] {
+                let set = lookup(value)?;
+                if let Ok(state) = value.parse::<u8>() {
+                    entries.push(Entry::State(state));
+                    continue;
+                }
+
+                let slot = match slots.iter().position(|slot| slot == value) {
+                    Some(slot) => slot,
+                    None => {
+                        slots.push(value);
+                        slots.len() - 1
+                    }
+                };
+                entries.push(Entry::Variable(set, slot));
+            }
+
+            let output = &values[expected - 1];
+            let output = match output.parse::<u8>() {
+                Ok(state) => {
+                    lookup(output)?;
+                    Entry::State(state)
+                }
+                Err(_) => match slots.iter().position(|slot| slot == output) {
+                    Some(slot) => Entry::Variable(lookup(output)?, slot),
+                    None => return Err(error(TableErrorKind::UnboundOutput(output.to_string())))
+                }
+            };
+
+            let state = entries.remove(0);
+            transitions.push(Transition { state, neighbors: entries, output });
+        }
+
+        let line = s.lines().count() + 1;
+        let missing = |section| TableError { line, kind: TableErrorKind::MissingSection(section) };
+        let name = name.ok_or_else(|| missing("@RULE"))?;
+        if !found_table {
+            return Err(missing("@TABLE"));
+        }
+        let neighbors = clockwise(neighborhood).len();
+
+        Ok(Table {
+            name,
+            states: states.ok_or_else(|| missing("n_states"))?,
+            neighborhood,
+            symmetries: symmetries.unwrap_or_else(|| Symmetries::Orders(vec![(0..neighbors).collect()])),
+            transitions
+        })
+    }
+}
+
+/// Where reading a rule table failed, lines start at 1.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TableError {
+    pub line: usize,
+    pub kind: TableErrorKind
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TableErrorKind {
+    /// The file has no `@RULE` name, `@TABLE` section or `n_states` line.
+    MissingSection(&'static str),
+    /// A variable or transition came before the `n_states` line.
+    MissingStates,
+    /// `n_states` isn't a number from 2 to 256.
+    InvalidStates(String),
+    UnsupportedNeighborhood(String),
+    /// Symmetries that are unknown or don't fit the neighborhood.
+    UnsupportedSymmetries(String),
+    /// Something that is neither a state below `n_states` nor a variable defined above.
+    UnknownValue(String),
+    /// A transition with the wrong number of states for the neighborhood, holds how many it should
+    /// have and how many it had.
+    WrongEntryCount(usize, usize),
+    /// A transition moving to a variable none of its inputs use.
+    UnboundOutput(String),
+    InvalidLine(String)
+}
+
+impl fmt::Display for TableError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: ", self.line)?;
+        match &self.kind {
+            TableErrorKind::MissingSection(section) => write!(f, "missing {}", section),
+            TableErrorKind::MissingStates => write!(f, "n_states has to come first"),
+            TableErrorKind::InvalidStates(states) => {
+                write!(f, "'{}' is not a number of states between 2 and 256", states)
+            }
+            TableErrorKind::UnsupportedNeighborhood(neighborhood) => {
+                write!(f, "unsupported neighborhood '{}'", neighborhood)
+            }
+            TableErrorKind::UnsupportedSymmetries(symmetries) => {
+                write!(f, "unsupported symmetries '{}' for the neighborhood", symmetries)
+            }
+            TableErrorKind::UnknownValue(value) => write!(f, "'{}' is not a state or a variable", value),
+            TableErrorKind::WrongEntryCount(expected, found) => {
+                write!(f, "expected {} states in a transition, found {}", expected, found)
+            }
+            TableErrorKind::UnboundOutput(output) => {
+                write!(f, "'{}' isn't one of the transition's inputs", output)
+            }
+            TableErrorKind::InvalidLine(line) => write!(f, "can't make sense of '{}'", line)
+        }
+    }
+}
+
+impl Error for TableError {}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::automaton::{StateEngine, Wireworld};
+    use crate::engine::Engine;
+    use crate::topology::Topology;
+    use crate::Cell;
+
+    /// A table named Test, the lines given start at line 3.
+    fn table(lines: &str) -> Result<Table, TableError> {
+        format!("@RULE Test\n@TABLE\n{}", lines).parse()
+    }
+
+    /// Neighbors in `StateRule::next_state` order from the ones set, as compass points.
+    fn neighbors(live: &[&str]) -> [u8; 8] {
+        let points = ["NW", "N", "NE", "W", "E", "SW", "S", "SE"];
+        let mut neighbors = [0; 8];
+        for point in live {
+            neighbors[points.iter().position(|other| other == point).unwrap()] = 1;
+        }
+        neighbors
+    }
+
+    const WIREWORLD: &str = "@RULE WireWorld
+@TABLE
+n_states:4
+neighborhood:Moore
+symmetries:permute
+var a={0,1,2,3}
+var b={0,1,2,3}
+var c={0,1,2,3}
+var d={0,1,2,3}
+var e={0,1,2,3}
+var f={0,1,2,3}
+var g={0,1,2,3}
+var h={0,1,2,3}
+var i={0,2,3}
+var j={0,2,3}
+var k={0,2,3}
+var l={0,2,3}
+var m={0,2,3}
+var n={0,2,3}
+var o={0,2,3}
+1,a,b,c,d,e,f,g,h,2
+2,a,b,c,d,e,f,g,h,3
+3,1,i,j,k,l,m,n,o,1
+3,1,1,i,j,k,l,m,n,1
+";
+
+    #[test]
+    fn errors_have_line_numbers() {
+        let error = |lines: &str| table(lines).map(|_| ()).unwrap_err();
+        let at = |line, kind| TableError { line, kind };
+
+        assert_eq!(error("0,1,0,0,0,1"), at(3, TableErrorKind::MissingStates));
+        assert_eq!(error("n_states:1"), at(3, TableErrorKind::InvalidStates("1".to_string())));
+        assert_eq!(error("n_states:257"), at(3, TableErrorKind::InvalidStates("257".to_string())));
+        assert_eq!(
+            error("n_states:2\nneighborhood:triangular"),
+            at(4, TableErrorKind::UnsupportedNeighborhood("triangular".to_string()))
+        );
+        assert_eq!(
+            error("n_states:2\nneighborhood:Moore\nsymmetries:rotate3"),
+            at(5, TableErrorKind::UnsupportedSymmetries("rotate3".to_string()))
+        );
+        assert_eq!(
+            error("n_states:2\nneighborhood:vonNeumann\n# comment\n\n0,1,0,0,2,1"),
+            at(7, TableErrorKind::UnknownValue("2".to_string()))
+        );
+        assert_eq!(
+            error("n_states:2\nneighborhood:vonNeumann\n0,1,0,0,1"),
+            at(5, TableErrorKind::WrongEntryCount(6, 5))
+        );
+        assert_eq!(
+            error("n_states:2\nneighborhood:vonNeumann\nvar a={0,1}\n0,1,0,0,0,a"),
+            at(6, TableErrorKind::UnboundOutput("a".to_string()))
+        );
+        assert_eq!(error("n_states:2\nvar a=0,1"), at(4, TableErrorKind::InvalidLine("var a=0,1".to_string())));
+        assert_eq!(
+            "@TABLE\nn_states:2\n".parse::<Table>(),
+            Err(at(3, TableErrorKind::MissingSection("@RULE")))
+        );
+        assert_eq!(table("").map(|_| ()), Err(at(3, TableErrorKind::MissingSection("n_states"))));
+    }
+
+    #[test]
+    fn state_counts() {
+        assert_eq!(table("n_states:256").unwrap().states(), 256);
+        let table = table("n_states:256\nneighborhood:vonNeumann\n0,255,0,0,0,255").unwrap();
+        assert_eq!(table.next_state(0, [0, 255, 0, 0, 0, 0, 0, 0]), 255);
+    }
+
+    #[test]
+    fn symmetries() {
+        // Born with live cells at the top and the top right only
+        let rule = |symmetries| {
+            table(&format!("n_states:2\nneighborhood:Moore\nsymmetries:{}\n0,1,1,0,0,0,0,0,0,1", symmetries)).unwrap()
+        };
+        // Live neighbors, then whether none, rotate4, reflect_horizontal and rotate4reflect match
+        let cases: [(&[&str], bool, bool, bool, bool); 5] = [
+            (&["N", "NE"], true, true, true, true),
+            (&["E", "SE"], false, true, false, true),
+            (&["N", "NW"], false, false, true, true),
+            (&["S", "SE"], false, false, false, true),
+            (&["N", "SE"], false, false, false, false)
+        ];
+        for &(live, none, rotate4, reflect, rotate4reflect) in &cases {
+            let name = live.join(" ");
+            let born = |symmetries| rule(symmetries).next_state(0, neighbors(live)) == 1;
+            assert_eq!(born("none"), none, "{} none", name);
+            assert_eq!(born("rotate4"), rotate4, "{} rotate4", name);
+            assert_eq!(born("reflect_horizontal"), reflect, "{} reflect_horizontal", name);
+            assert_eq!(born("rotate4reflect"), rotate4reflect, "{} rotate4reflect", name);
+        }
+
+        // Permuted any two live neighbors will do
+        let permute = rule("permute");
+        assert_eq!(permute.next_state(0, neighbors(&["N", "SE"])), 1);
+        assert_eq!(permute.next_state(0, neighbors(&["SW", "W"])), 1);
+        assert_eq!(permute.next_state(0, neighbors(&["SW", "W", "E"])), 0);
+    }
+
+    #[test]
+    fn repeated_variables_bind_once() {
+        let table = table("n_states:3\nneighborhood:vonNeumann\nvar a={1,2}\n0,a,0,a,0,a").unwrap();
+        let von_neumann = |north: u8, south: u8| [0, north, 0, 0, 0, 0, south, 0];
+
+        assert_eq!(table.next_state(0, von_neumann(1, 1)), 1);
+        assert_eq!(table.next_state(0, von_neumann(2, 2)), 2);
+        assert_eq!(table.next_state(0, von_neumann(1, 2)), 0);
+        assert_eq!(table.next_state(0, von_neumann(0, 0)), 0);
+    }
+
+    #[test]
+    fn transitions_without_commas() {
+        let with_commas = table("n_states:3\nneighborhood:vonNeumann\nsymmetries:rotate4\n0,1,2,0,0,2\n2,0,0,0,0,1").unwrap();
+        let without = table("n_states:3\nneighborhood:vonNeumann\nsymmetries:rotate4\n012002\n2 0 0 0 0 1").unwrap();
+        assert_eq!(with_commas, without);
+        assert_eq!(without.next_state(0, [0, 0, 0, 0, 1, 0, 2, 0]), 2);
+        assert_eq!(without.next_state(2, [0; 8]), 1);
+    }
+
+    #[test]
+    fn wireworld_table_matches_built_in() {
+        let table: Table = WIREWORLD.parse().unwrap();
+        // A loop of wire with an electron going round and a wire leading off it
+        let mut seed: HashMap<Cell, u8> = HashMap::new();
+        for col in 0..8 {
+            seed.insert((0, col), Wireworld::CONDUCTOR);
+            seed.insert((3, col), Wireworld::CONDUCTOR);
+        }
+        for row in 1..3 {
+            seed.insert((row, -1), Wireworld::CONDUCTOR);
+            seed.insert((row, 8), Wireworld::CONDUCTOR);
+        }
+        for col in 9..16 {
+            seed.insert((2, col), Wireworld::CONDUCTOR);
+        }
+        seed.insert((0, 1), Wireworld::TAIL);
+        seed.insert((0, 2), Wireworld::HEAD);
+
+        let mut expected = StateEngine::new(seed.clone(), Wireworld, Topology::Plane).unwrap();
+        let mut engine = StateEngine::new(seed, table, Topology::Plane).unwrap();
+        for generation in 0..40 {
+            assert_eq!(engine.states(), expected.states(), "generation {}", generation);
+            engine.step();
+            expected.step();
+        }
+    }
+}
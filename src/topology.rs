@@ -0,0 +1,69 @@
+use crate::coordinate::Coordinate;
+use crate::{Cell, CellCoordinate};
+
+/// The shape of the universe cells live in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Topology<C: Coordinate = CellCoordinate> {
+    /// Unbounded plane only limited by the range of the Coordinate type. Neighbors that would fall
+    /// outside of that range are dropped.
+    #[default]
+    Plane,
+    /// Finite board of `width` columns by `height` rows where neighbors wrap across the edges.
+    ///
+    /// Cells are kept in `0..height` rows and `0..width` columns, build it with
+    /// `Topology::torus` so the size is always positive.
+    Torus { width: C, height: C }
+}
+
+impl<C: Coordinate> Topology<C> {
+    /// Returns None unless both sides of the board are positive.
+    pub fn torus(width: C, height: C) -> Option<Topology<C>> {
+        if width <= C::ZERO || height <= C::ZERO {
+            return None;
+        }
+
+        Some(Topology::Torus { width, height })
+    }
+
+    /// Maps any cell onto the universe, on a torus this folds the cell back onto the board.
+    pub fn wrap(&self, (row, col): Cell<C>) -> Cell<C> {
+        match *self {
+            Topology::Plane => (row, col),
+            Topology::Torus { width, height } => (row.rem_euclid(height), col.rem_euclid(width))
+        }
+    }
+
+    /// Moves `cell` by `(row_offset, col_offset)`, each being -1, 0 or 1.
+    ///
+    /// On the plane None is returned when the neighbor can't be represented by the Coordinate type.
+    pub fn neighbor(&self, (row, col): Cell<C>, (row_offset, col_offset): (i8, i8)) -> Option<Cell<C>> {
+        match *self {
+            Topology::Plane => Some((
+                offset_plane(row, row_offset)?,
+                offset_plane(col, col_offset)?
+            )),
+            Topology::Torus { width, height } => Some((
+                offset_torus(row, row_offset, height),
+                offset_torus(col, col_offset, width)
+            ))
+        }
+    }
+}
+
+fn offset_plane<C: Coordinate>(val: C, offset: i8) -> Option<C> {
+    match offset {
+        -1 => val.checked_sub(C::ONE),
+        1 => val.checked_add(C::ONE),
+        _ => Some(val)
+    }
+}
+
+fn offset_torus<C: Coordinate>(val: C, offset: i8, size: C) -> C {
+    match offset {
+        -1 if val == C::ZERO => size - C::ONE,
+        -1 => val - C::ONE,
+        1 if val + C::ONE >= size => C::ZERO,
+        1 => val + C::ONE,
+        _ => val
+    }
+}
@@ -0,0 +1,441 @@
+use std::collections::HashSet;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::thread;
+use std::time::Instant;
+
+use rust_conway_gol::coordinate::Coordinate;
+use rust_conway_gol::engine::Backend;
+use rust_conway_gol::pattern::{self, Format, Pattern};
+use rust_conway_gol::rule::AnyRule;
+use rust_conway_gol::topology::Topology;
+use rust_conway_gol::{bounding_box, Cell, CellCoordinate, GOLGenerationIterator};
+
+use super::terminal::{Key, Terminal};
+use super::viewer::Viewer;
+use super::{IDLE, RESIZE_CHECK};
+
+const KEYS: &str =
+    "arrows move  space toggle  v select  y copy  x cut  p paste  r rotate  m/M mirror  w save  g run  q quit";
+
+/// Cells and their states relative to the top left corner of a `height` by `width` rectangle,
+/// what copies and the transforms work on.
+#[derive(Debug, Clone)]
+struct Block {
+    cells: Vec<((i128, i128), u8)>,
+    height: i128,
+    width: i128
+}
+
+impl Block {
+    /// A quarter turn clockwise.
+    fn rotate(&self) -> Block {
+        Block {
+            cells: self.cells.iter().map(|&((row, col), state)| ((col, self.height - 1 - row), state)).collect(),
+            height: self.width,
+            width: self.height
+        }
+    }
+
+    /// Left to right.
+    fn mirror(&self) -> Block {
+        Block {
+            cells: self.cells.iter().map(|&((row, col), state)| ((row, self.width - 1 - col), state)).collect(),
+            ..*self
+        }
+    }
+
+    /// Top to bottom.
+    fn flip(&self) -> Block {
+        Block {
+            cells: self.cells.iter().map(|&((row, col), state)| ((self.height - 1 - row, col), state)).collect(),
+            ..*self
+        }
+    }
+}
+
+/// Full screen editor for a seed, one cell per two characters.
+pub struct Editor {
+    cells: HashSet<Cell>,
+    /// Name, author and comments of the file being edited, kept for saving, and the states of
+    /// cells that aren't simply alive
+    pattern: Pattern,
+    rule: AnyRule,
+    path: Option<PathBuf>,
+    cursor: Cell,
+    /// Corner of the selection opposite the cursor
+    anchor: Option<Cell>,
+    clipboard: Option<Block>,
+    /// Top left cell on screen
+    view: Cell,
+    /// File name being typed in after pressing w
+    prompt: Option<String>,
+    message: String
+}
+
+impl Editor {
+    pub fn new(mut pattern: Pattern, rule: AnyRule, path: Option<PathBuf>) -> Editor {
+        let cells: HashSet<Cell> = pattern.cells.drain(..).collect();
+        let cursor = bounding_box(&cells).map_or((0, 0), |(top_left, _)| top_left);
+
+        Editor {
+            cells,
+            pattern,
+            rule,
+            path,
+            cursor,
+            anchor: None,
+            clipboard: None,
+            view: (cursor.0.saturating_sub(5), cursor.1.saturating_sub(5)),
+            prompt: None,
+            message: String::new()
+        }
+    }
+
+    /// Runs until q is pressed.
+    pub fn run(mut self, terminal: &mut Terminal) -> io::Result<()> {
+        let mut size = terminal.size()?;
+        let mut size_checked = Instant::now();
+        let mut dirty = true;
+
+        loop {
+            for key in terminal.keys()? {
+                if self.prompt.is_some() {
+                    self.prompt_key(key);
+                } else if !self.key(key, terminal)? {
+                    return Ok(());
+                }
+                dirty = true;
+            }
+
+            if size_checked.elapsed() >= RESIZE_CHECK {
+                let new_size = terminal.size()?;
+                dirty |= new_size != size;
+                size = new_size;
+                size_checked = Instant::now();
+            }
+
+            if dirty {
+                terminal.draw(&self.frame(size))?;
+                dirty = false;
+            } else {
+                thread::sleep(IDLE);
+            }
+        }
+    }
+
+    /// Handles a key outside of the save prompt, false once the editor should close.
+    fn key(&mut self, key: Key, terminal: &mut Terminal) -> io::Result<bool> {
+        self.message.clear();
+        let (row, col) = self.cursor;
+
+        match key {
+            Key::Char('q') | Key::Char('\x03') => return Ok(false),
+            Key::Up => self.cursor = (row.saturating_sub(1), col),
+            Key::Down => self.cursor = (row.saturating_add(1), col),
+            Key::Left => self.cursor = (row, col.saturating_sub(1)),
+            Key::Right => self.cursor = (row, col.saturating_add(1)),
+            Key::Char(' ') | Key::Enter => self.toggle(),
+            Key::Char('v') => self.anchor = if self.anchor.is_some() { None } else { Some(self.cursor) },
+            Key::Escape => self.anchor = None,
+            Key::Char('y') => self.copy(false),
+            Key::Char('x') => self.copy(true),
+            Key::Char('p') => self.paste(),
+            Key::Char('r') => self.transform(Block::rotate),
+            Key::Char('m') => self.transform(Block::mirror),
+            Key::Char('M') => self.transform(Block::flip),
+            Key::Char('w') => {
+                let path = self.path.as_ref().map_or("pattern.rle".to_string(), |path| path.display().to_string());
+                self.prompt = Some(path);
+            }
+            Key::Char('g') => {
+                let seed = self.cells.iter().map(|cell| (*cell, self.state(cell))).collect();
+                match GOLGenerationIterator::with_states(seed, self.rule.clone(), Topology::Plane, Backend::HashSet) {
+                    Ok(generations) => Viewer::new(generations).run(terminal)?,
+                    Err(err) => self.message = err.to_string()
+                }
+            }
+            _ => {}
+        }
+
+        Ok(true)
+    }
+
+    fn prompt_key(&mut self, key: Key) {
+        let prompt = match self.prompt.as_mut() {
+            Some(prompt) => prompt,
+            None => return
+        };
+
+        match key {
+            Key::Char(c) if !c.is_control() => prompt.push(c),
+            Key::Backspace => {
+                prompt.pop();
+            }
+            Key::Escape => self.prompt = None,
+            Key::Enter => {
+                let path = PathBuf::from(self.prompt.take().unwrap_or_default());
+                self.save(path);
+            }
+            _ => {}
+        }
+    }
+
+    /// Writes the cells as RLE, the outcome goes on the message line.
+    fn save(&mut self, path: PathBuf) {
+        let mut cells: Vec<Cell> = self.cells.iter().copied().collect();
+        cells.sort_unstable();
+
+        let pattern = Pattern {
+            cells,
+            rule: Some(self.rule.clone()),
+            ..self.pattern.clone()
+        };
+
+        match fs::write(&path, pattern::write(&pattern, Format::Rle)) {
+            Ok(()) => {
+                self.message = format!("saved {}", path.display());
+                self.path = Some(path);
+            }
+            Err(err) => self.message = format!("{}: {}", path.display(), err)
+        }
+    }
+
+    /// Cells toggled on are alive whatever state they were in before.
+    fn toggle(&mut self) {
+        self.pattern.states.remove(&self.cursor);
+        if !self.cells.remove(&self.cursor) {
+            self.cells.insert(self.cursor);
+        }
+    }
+
+    fn state(&self, cell: &Cell) -> u8 {
+        self.pattern.states.get(cell).copied().unwrap_or(1)
+    }
+
+    fn set(&mut self, cell: Cell, state: u8) {
+        self.cells.insert(cell);
+        if state == 1 {
+            self.pattern.states.remove(&cell);
+        } else {
+            self.pattern.states.insert(cell, state);
+        }
+    }
+
+    /// Selection as its (top left, bottom right) corners.
+    fn selection(&self) -> Option<(Cell, Cell)> {
+        let (anchor_row, anchor_col) = self.anchor?;
+        let (row, col) = self.cursor;
+        Some((
+            (anchor_row.min(row), anchor_col.min(col)),
+            (anchor_row.max(row), anchor_col.max(col))
+        ))
+    }
+
+    /// Live cells inside the rectangle as a block, removing them when `cut` is set.
+    fn take(&mut self, ((top, left), (bottom, right)): (Cell, Cell), cut: bool) -> Block {
+        let inside: Vec<Cell> = self
+            .cells
+            .iter()
+            .filter(|&&(row, col)| (top..=bottom).contains(&row) && (left..=right).contains(&col))
+            .copied()
+            .collect();
+
+        let cells = inside
+            .iter()
+            .map(|&(row, col)| ((row as i128 - top as i128, col as i128 - left as i128), self.state(&(row, col))))
+            .collect();
+
+        if cut {
+            for cell in &inside {
+                self.cells.remove(cell);
+                self.pattern.states.remove(cell);
+            }
+        }
+
+        Block {
+            cells,
+            height: bottom as i128 - top as i128 + 1,
+            width: right as i128 - left as i128 + 1
+        }
+    }
+
+    /// Sets the cells of `block` with its top left corner at `(top, left)`, cells that would fall
+    /// off the edge of the coordinate range are dropped.
+    fn put(&mut self, block: &Block, (top, left): Cell) {
+        for &((row, col), state) in &block.cells {
+            let row = CellCoordinate::from_i128(top as i128 + row);
+            let col = CellCoordinate::from_i128(left as i128 + col);
+            if let (Some(row), Some(col)) = (row, col) {
+                self.set((row, col), state);
+            }
+        }
+    }
+
+    fn copy(&mut self, cut: bool) {
+        let selection = match self.selection() {
+            Some(selection) => selection,
+            None => {
+                self.message = "select something first with v".to_string();
+                return;
+            }
+        };
+
+        let block = self.take(selection, cut);
+        self.message = format!("{} {}x{}", if cut { "cut" } else { "copied" }, block.width, block.height);
+        self.clipboard = Some(block);
+        self.anchor = None;
+    }
+
+    fn paste(&mut self) {
+        match self.clipboard.clone() {
+            Some(block) => self.put(&block, self.cursor),
+            None => self.message = "nothing to paste".to_string()
+        }
+    }
+
+    /// Applies `transform` to the selection in place, or to the clipboard when nothing is selected.
+    fn transform(&mut self, transform: fn(&Block) -> Block) {
+        if let Some(selection) = self.selection() {
+            let block = transform(&self.take(selection, true));
+            let top_left = selection.0;
+            self.put(&block, top_left);
+
+            // Keep the selection around the result, a rotation swaps its sides
+            self.anchor = Some(top_left);
+            self.cursor = (
+                (top_left.0 as i128 + block.height - 1).min(CellCoordinate::MAX as i128) as CellCoordinate,
+                (top_left.1 as i128 + block.width - 1).min(CellCoordinate::MAX as i128) as CellCoordinate
+            );
+        } else if let Some(block) = &self.clipboard {
+            self.clipboard = Some(transform(block));
+            self.message = "transformed the clipboard".to_string();
+        } else {
+            self.message = "select something or copy it first".to_string();
+        }
+    }
+
+    /// Screen lines, the cells then a status line and a line for messages or the save prompt.
+    fn frame(&mut self, (rows, cols): (usize, usize)) -> Vec<String> {
+        let (height, width) = (rows.saturating_sub(2).max(1) as i128, (cols / 2).max(1) as i128);
+
+        // Scroll just far enough to keep the cursor on screen
+        let scroll = |view: CellCoordinate, cursor: CellCoordinate, size: i128| {
+            let (view, cursor) = (view as i128, cursor as i128);
+            let view = view.min(cursor).max(cursor - size + 1);
+            view.clamp(CellCoordinate::MIN as i128, CellCoordinate::MAX as i128) as CellCoordinate
+        };
+        self.view = (scroll(self.view.0, self.cursor.0, height), scroll(self.view.1, self.cursor.1, width));
+
+        let selection = self.selection();
+        let mut lines = vec![];
+        for screen_row in 0..height {
+            let mut line = String::new();
+            for screen_col in 0..width {
+                let cell = (
+                    CellCoordinate::from_i128(self.view.0 as i128 + screen_row),
+                    CellCoordinate::from_i128(self.view.1 as i128 + screen_col)
+                );
+                let cell = match cell {
+                    (Some(row), Some(col)) => (row, col),
+                    _ => {
+                        line.push_str("  ");
+                        continue;
+                    }
+                };
+
+                let alive = self.cells.contains(&cell);
+                let selected = selection.is_some_and(|((top, left), (bottom, right))| {
+                    (top..=bottom).contains(&cell.0) && (left..=right).contains(&cell.1)
+                });
+
+                // Cursor in yellow, selection on a blue background
+                let glyph = match (cell == self.cursor, alive) {
+                    (true, true) => "\x1b[33m▓▓",
+                    (true, false) => "\x1b[33m░░",
+                    (false, true) => "██",
+                    (false, false) => "· "
+                };
+                if selected {
+                    line.push_str("\x1b[44m");
+                }
+                line.push_str(glyph);
+                line.push_str("\x1b[0m");
+            }
+            lines.push(line);
+        }
+
+        let mut status = format!("x {} y {}  pop {}", self.cursor.1, self.cursor.0, self.cells.len());
+        if let Some(((top, left), (bottom, right))) = selection {
+            status.push_str(&format!("  selection {}x{}", right as i128 - left as i128 + 1, bottom as i128 - top as i128 + 1));
+        }
+        if let Some(block) = &self.clipboard {
+            status.push_str(&format!("  clipboard {}x{}", block.width, block.height));
+        }
+        status.push_str(&format!("  {}", self.rule));
+        if let Some(path) = &self.path {
+            status.push_str(&format!("  {}", path.display()));
+        }
+
+        let bottom = match &self.prompt {
+            Some(prompt) => format!("save as: {}", prompt),
+            None if !self.message.is_empty() => self.message.clone(),
+            None => KEYS.to_string()
+        };
+
+        lines.push(format!("\x1b[7m{}\x1b[0m", status.chars().take(cols).collect::<String>()));
+        lines.push(bottom.chars().take(cols).collect());
+        lines
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    /// An editor on a live cell at (0, 0) with a dying one to its right.
+    fn editor() -> Editor {
+        let mut pattern = Pattern::new(vec![(0, 0), (0, 1)]);
+        pattern.states.insert((0, 1), 2);
+        Editor::new(pattern, "B2/S/C3".parse().unwrap(), None)
+    }
+
+    fn states(editor: &Editor) -> HashMap<Cell, u8> {
+        editor.cells.iter().map(|&cell| (cell, editor.state(&cell))).collect()
+    }
+
+    #[test]
+    fn toggled_cells_are_alive() {
+        let mut editor = editor();
+        editor.cursor = (0, 1);
+        editor.toggle();
+        editor.toggle();
+        assert_eq!(states(&editor), vec![((0, 0), 1), ((0, 1), 1)].into_iter().collect());
+        assert!(editor.pattern.states.is_empty());
+    }
+
+    #[test]
+    fn states_move_with_their_cells() {
+        let mut editor = editor();
+        editor.anchor = Some((0, 0));
+        editor.cursor = (1, 1);
+        editor.transform(Block::rotate);
+        assert_eq!(states(&editor), vec![((0, 1), 1), ((1, 1), 2)].into_iter().collect());
+
+        editor.copy(true);
+        assert!(editor.cells.is_empty());
+        assert!(editor.pattern.states.is_empty());
+
+        editor.cursor = (5, 5);
+        editor.paste();
+        assert_eq!(states(&editor), vec![((5, 6), 1), ((6, 6), 2)].into_iter().collect());
+
+        // Pasting over a dying cell leaves whatever was pasted
+        editor.clipboard = Some(Block { cells: vec![((0, 0), 1)], height: 1, width: 1 });
+        editor.cursor = (6, 6);
+        editor.paste();
+        assert_eq!(states(&editor), vec![((5, 6), 1), ((6, 6), 1)].into_iter().collect());
+    }
+}
@@ -0,0 +1,17 @@
+//! Full screen terminal interfaces.
+
+use std::time::Duration;
+
+mod editor;
+mod terminal;
+mod viewer;
+
+pub use editor::Editor;
+pub use terminal::Terminal;
+pub use viewer::Viewer;
+
+/// How long to wait between looking for keys when there's nothing else to do.
+const IDLE: Duration = Duration::from_millis(10);
+
+/// How often the terminal size is checked.
+const RESIZE_CHECK: Duration = Duration::from_millis(500);
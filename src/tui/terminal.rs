@@ -0,0 +1,141 @@
+//! Just enough terminal handling for the full screen UIs without pulling in a dependency. Raw mode
+//! is switched on and off with `stty` and everything else is ANSI escape codes, so this works on
+//! Unix terminals only.
+
+use std::fs::{File, OpenOptions};
+use std::io::{self, Read, Write};
+use std::process::Command;
+
+/// Switches to the alternate screen, hides the cursor, stops lines wrapping and clears.
+const ENTER: &str = "\x1b[?1049h\x1b[?25l\x1b[?7l\x1b[2J";
+/// Wraps lines again, shows the cursor and goes back to the normal screen.
+const LEAVE: &str = "\x1b[?7h\x1b[?25h\x1b[?1049l";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Key {
+    Char(char),
+    Enter,
+    Backspace,
+    Escape,
+    Up,
+    Down,
+    Left,
+    Right
+}
+
+/// The controlling terminal in raw mode, put back the way it was when dropped.
+pub struct Terminal {
+    tty: File,
+    /// `stty -g` output from before raw mode was switched on
+    saved: String
+}
+
+impl Terminal {
+    pub fn open() -> io::Result<Terminal> {
+        let tty = OpenOptions::new().read(true).write(true).open("/dev/tty")?;
+        let saved = stty(&tty, &["-g"])?;
+        // min 0 time 0 makes reads return straight away when no key is waiting
+        stty(&tty, &["raw", "-echo", "min", "0", "time", "0"])?;
+
+        let mut terminal = Terminal { tty, saved: saved.trim().to_string() };
+        terminal.tty.write_all(ENTER.as_bytes())?;
+        Ok(terminal)
+    }
+
+    /// (rows, columns) of the terminal.
+    pub fn size(&self) -> io::Result<(usize, usize)> {
+        let size = stty(&self.tty, &["size"])?;
+        match size.split_whitespace().map(str::parse).collect::<Vec<_>>()[..] {
+            [Ok(rows), Ok(cols)] => Ok((rows, cols)),
+            _ => Err(io::Error::other(format!("unexpected terminal size '{}'", size.trim())))
+        }
+    }
+
+    /// Keys pressed since the last call, empty if there weren't any.
+    pub fn keys(&mut self) -> io::Result<Vec<Key>> {
+        let mut bytes = vec![];
+        let mut buffer = [0; 64];
+        loop {
+            match self.tty.read(&mut buffer)? {
+                0 => break,
+                read => bytes.extend_from_slice(&buffer[..read])
+            }
+        }
+
+        Ok(parse_keys(&String::from_utf8_lossy(&bytes)))
+    }
+
+    /// Replaces the screen with `lines`. Wrapping is off so the terminal cuts lines wider than it
+    /// and the rest of shorter ones is cleared.
+    pub fn draw(&mut self, lines: &[String]) -> io::Result<()> {
+        let mut frame = String::from("\x1b[H");
+        for (index, line) in lines.iter().enumerate() {
+            if index > 0 {
+                frame.push_str("\r\n");
+            }
+            frame.push_str(line);
+            frame.push_str("\x1b[K");
+        }
+        frame.push_str("\x1b[J");
+
+        self.tty.write_all(frame.as_bytes())?;
+        self.tty.flush()
+    }
+}
+
+impl Drop for Terminal {
+    fn drop(&mut self) {
+        // Nothing sensible can be done if restoring fails, the shell's `reset` will still work
+        let _ = self.tty.write_all(LEAVE.as_bytes());
+        let _ = stty(&self.tty, &[self.saved.as_str()]);
+    }
+}
+
+/// Runs stty against the terminal and returns what it printed.
+fn stty(tty: &File, args: &[&str]) -> io::Result<String> {
+    let output = Command::new("stty").args(args).stdin(tty.try_clone()?).output()?;
+    if !output.status.success() {
+        return Err(io::Error::other(String::from_utf8_lossy(&output.stderr).trim().to_string()));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Splits raw input into keys, escape sequences other than the arrow keys are dropped. An escape
+/// on its own is the escape key.
+fn parse_keys(input: &str) -> Vec<Key> {
+    let mut keys = vec![];
+    let mut chars = input.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c != '\x1b' || !matches!(chars.peek(), Some('[') | Some('O')) {
+            keys.push(match c {
+                '\r' | '\n' => Key::Enter,
+                '\x7f' | '\x08' => Key::Backspace,
+                '\x1b' => Key::Escape,
+                c => Key::Char(c)
+            });
+            continue;
+        }
+
+        chars.next();
+        // Parameters then a single final character between @ and ~
+        let mut last = None;
+        for c in chars.by_ref() {
+            if ('@'..='~').contains(&c) {
+                last = Some(c);
+                break;
+            }
+        }
+
+        match last {
+            Some('A') => keys.push(Key::Up),
+            Some('B') => keys.push(Key::Down),
+            Some('C') => keys.push(Key::Right),
+            Some('D') => keys.push(Key::Left),
+            _ => {}
+        }
+    }
+
+    keys
+}
@@ -0,0 +1,194 @@
+use std::collections::HashMap;
+use std::io;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use rust_conway_gol::render::{render_zoomed_states, Viewport, Zoom};
+use rust_conway_gol::{bounding_box, Cell, CellCoordinate, GOLGenerationIterator, StatesIterator};
+
+use super::terminal::{Key, Terminal};
+use super::{IDLE, RESIZE_CHECK};
+
+/// Generations per second to pick from with + and -.
+const SPEEDS: [u32; 10] = [1, 2, 5, 10, 20, 50, 100, 200, 500, 1000];
+
+/// Longest time spent stepping before the screen is redrawn, so keys are still picked up when the
+/// engine can't keep up with the speed.
+const FRAME_BUDGET: Duration = Duration::from_millis(50);
+
+const KEYS: &str = "space play/pause  n step  +/- speed  arrows pan  z/Z zoom  f follow  q quit";
+
+/// Full screen player for a running universe.
+pub struct Viewer {
+    generations: StatesIterator<CellCoordinate>,
+    /// Every cell that isn't dead, dying cells of Generations rules included
+    current: HashMap<Cell, u8>,
+    generation: u64,
+    /// Cell in the middle of the screen
+    center: (i128, i128),
+    zoom: Zoom,
+    speed: usize,
+    playing: bool,
+    follow: bool
+}
+
+impl Viewer {
+    pub fn new(generations: GOLGenerationIterator<CellCoordinate>) -> Viewer {
+        let mut generations = generations.into_states();
+        let current = generations.next().unwrap_or_default();
+        let mut viewer = Viewer {
+            generations,
+            current,
+            generation: 0,
+            center: (0, 0),
+            zoom: Zoom::Wide,
+            speed: 3,
+            playing: false,
+            follow: true
+        };
+        viewer.center_on_pattern();
+        viewer
+    }
+
+    /// Runs until q is pressed.
+    pub fn run(mut self, terminal: &mut Terminal) -> io::Result<()> {
+        let mut size = terminal.size()?;
+        let mut size_checked = Instant::now();
+        let mut next_step = Instant::now();
+        let mut dirty = true;
+
+        loop {
+            for key in terminal.keys()? {
+                match key {
+                    // Ctrl-C doesn't raise a signal in raw mode
+                    Key::Char('q') | Key::Char('\x03') => return Ok(()),
+                    Key::Char(' ') => {
+                        self.playing = !self.playing;
+                        next_step = Instant::now();
+                    }
+                    Key::Char('n') | Key::Char('.') => {
+                        self.playing = false;
+                        self.step();
+                    }
+                    Key::Char('+') | Key::Char('=') => self.speed = (self.speed + 1).min(SPEEDS.len() - 1),
+                    Key::Char('-') => self.speed = self.speed.saturating_sub(1),
+                    Key::Char('z') => self.zoom = self.zoom.zoom_out(),
+                    Key::Char('Z') => self.zoom = self.zoom.zoom_in(),
+                    Key::Char('f') => self.follow = !self.follow,
+                    Key::Up | Key::Down | Key::Left | Key::Right => {
+                        self.follow = false;
+                        self.pan(key, size);
+                    }
+                    _ => continue
+                }
+                dirty = true;
+            }
+
+            if self.playing {
+                let interval = Duration::from_secs(1) / SPEEDS[self.speed];
+                let started = Instant::now();
+                while next_step <= Instant::now() && started.elapsed() < FRAME_BUDGET {
+                    self.step();
+                    next_step += interval;
+                    dirty = true;
+                }
+
+                // Fell behind, carry on from now rather than trying to catch up
+                if next_step < Instant::now() {
+                    next_step = Instant::now();
+                }
+            }
+
+            if size_checked.elapsed() >= RESIZE_CHECK {
+                let new_size = terminal.size()?;
+                dirty |= new_size != size;
+                size = new_size;
+                size_checked = Instant::now();
+            }
+
+            if dirty {
+                terminal.draw(&self.frame(size))?;
+                dirty = false;
+            } else {
+                thread::sleep(IDLE);
+            }
+        }
+    }
+
+    fn step(&mut self) {
+        if let Some(next) = self.generations.next() {
+            self.current = next;
+            self.generation += 1;
+        }
+    }
+
+    fn center_on_pattern(&mut self) {
+        if let Some(((top, left), (bottom, right))) = bounding_box(self.current.keys()) {
+            self.center = (
+                (top as i128 + bottom as i128) / 2,
+                (left as i128 + right as i128) / 2
+            );
+        }
+    }
+
+    /// Moves an eighth of the screen in the direction of the arrow.
+    fn pan(&mut self, key: Key, (rows, cols): (usize, usize)) {
+        let (cell_rows, cell_cols) = self.zoom.cells_for(rows.saturating_sub(1), cols);
+        let (row_step, col_step) = ((cell_rows as i128 / 8).max(1), (cell_cols as i128 / 8).max(1));
+
+        match key {
+            Key::Up => self.center.0 -= row_step,
+            Key::Down => self.center.0 += row_step,
+            Key::Left => self.center.1 -= col_step,
+            Key::Right => self.center.1 += col_step,
+            _ => {}
+        }
+    }
+
+    /// Screen lines for the current generation, the universe then a status line.
+    fn frame(&mut self, (rows, cols): (usize, usize)) -> Vec<String> {
+        let universe_rows = rows.saturating_sub(1);
+
+        if self.follow {
+            self.center_on_pattern();
+
+            // Zoom out until the whole pattern fits, or as far as it goes
+            if let Some(((top, left), (bottom, right))) = bounding_box(self.current.keys()) {
+                let (height, width) = (bottom as i128 - top as i128 + 1, right as i128 - left as i128 + 1);
+                loop {
+                    let (cell_rows, cell_cols) = self.zoom.cells_for(universe_rows, cols);
+                    if (height <= cell_rows as i128 && width <= cell_cols as i128) || self.zoom == Zoom::Braille {
+                        break;
+                    }
+                    self.zoom = self.zoom.zoom_out();
+                }
+            }
+        }
+
+        let (height, width) = self.zoom.cells_for(universe_rows, cols);
+        let clamp = |value: i128| value.clamp(CellCoordinate::MIN as i128, CellCoordinate::MAX as i128) as CellCoordinate;
+        let viewport = Viewport {
+            top: clamp(self.center.0 - height as i128 / 2),
+            left: clamp(self.center.1 - width as i128 / 2),
+            width,
+            height
+        };
+
+        let mut lines = render_zoomed_states(&self.current, &viewport, self.zoom);
+        lines.truncate(universe_rows);
+
+        let status = format!(
+            "gen {}  pop {}  {}/s{}  {:?}{}  |  {}",
+            self.generation,
+            self.current.values().filter(|&&state| state == 1).count(),
+            SPEEDS[self.speed],
+            if self.playing { "" } else { " paused" },
+            self.zoom,
+            if self.follow { "  following" } else { "" },
+            KEYS
+        );
+        lines.push(format!("\x1b[7m{}\x1b[0m", status.chars().take(cols).collect::<String>()));
+
+        lines
+    }
+}